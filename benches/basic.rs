@@ -1,118 +1,394 @@
 use criterion::{
-    criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, Criterion,
+    criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, BenchmarkId, Criterion,
+    Throughput,
 };
-use genbench::Crate;
+use genbench::harness::{self, Mix, Mode, Op, Workload};
+use genbench::memory::{self, Record};
+use genbench::metadata::{self, Metadata};
+use genbench::{Capabilities, Crate, Payload};
 use oorandom::Rand32;
+use std::path::Path;
 
-pub fn tests() -> [Box<dyn Crate>; 18] {
-    [
-        Box::new(genbench::bvmap::CrateBvMap()),
-        Box::new(genbench::stash::CrateStash()),
-        Box::new(genbench::stash::CrateUniqueStash()),
-        Box::new(genbench::slotmap::CrateSlotMap()),
-        Box::new(genbench::slotmap::CrateHopSlotMap()),
-        Box::new(genbench::slotmap::CrateDenseSlotMap()),
-        Box::new(genbench::slab::CrateSlab()),
-        Box::new(genbench::beach_map::CrateBeachMap()),
-        Box::new(genbench::stable_vec::CrateExternStableVec()),
-        Box::new(genbench::stable_vec::CrateInlineStableVec()),
-        Box::new(genbench::id_vec::CrateIdVec()),
-        Box::new(genbench::compactmap::CrateCompactMap()),
-        Box::new(genbench::generational_arena::CrateGenerationalArena()),
-        Box::new(genbench::naive::CrateMooSlotMap()),
-        Box::new(genbench::thunderdome::CrateThunderdome()),
-        Box::new(genbench::pulz_arena::CratePulzArena()),
-        Box::new(genbench::indextree::CrateIndextree()),
-        Box::new(genbench::generational_indextree::CrateGenerationalIndextree()),
-    ]
-}
-
-fn configure<'a>(c: &'a mut Criterion, name: &'a str) -> BenchmarkGroup<'a, WallTime> {
-    let mut g = c.benchmark_group(name);
-    g.sample_size(200);
-    g
-}
-
-fn inserts(c: &mut Criterion) {
-    let size = 10_000;
-    let mut g = configure(c, "Insert");
-    for test in self::tests() {
-        g.bench_function(test.name(), |b| {
-            test.insert(b, size);
-        });
+#[global_allocator]
+static ALLOCATOR: memory::Counting = memory::Counting;
+
+fn group<'a, T: Payload>(c: &'a mut Criterion, w: &Workload) -> BenchmarkGroup<'a, WallTime> {
+    c.benchmark_group(format!("{}/{}", w.group, T::NAME))
+}
+
+/// Criterion set up for the profile chosen with `GENBENCH_PROFILE`. Command line options such as
+/// `--sample-size` still take precedence.
+fn configure() -> Criterion {
+    let profile = harness::profile();
+    let c = Criterion::default()
+        .sample_size(profile.sample_size)
+        .warm_up_time(profile.warm_up)
+        .measurement_time(profile.measurement)
+        .nresamples(profile.resamples);
+    if profile.plots {
+        c
+    } else {
+        c.without_plots()
     }
 }
 
-fn reinserts(c: &mut Criterion) {
-    let size = 10_000;
-    let mut g = configure(c, "InsertUsed");
-    for test in self::tests() {
-        g.bench_function(test.name(), |b| {
-            test.reinsert(b, size);
+/// Checks that every selected implementation does the same work, saving the heap usage each one had
+/// along the way, then benchmarks each of them at `size`. `elements` is the number of elements the timed
+/// routine touches, reported as throughput.
+fn run<T: Payload>(
+    g: &mut BenchmarkGroup<WallTime>,
+    w: &Workload,
+    size: usize,
+    elements: usize,
+    f: impl Fn(&dyn Crate<T>, Mode),
+) {
+    let selection = harness::crate_selection();
+    let mut tests = genbench::crates::<T>();
+    tests.retain(|test| {
+        test.capabilities().satisfies(&w.requires) && selection.picks_crate(test.as_ref())
+    });
+    // `cargo bench -- --list` only prints benchmark names, so there is nothing to check.
+    if !std::env::args().any(|arg| arg == "--list") {
+        check(w, size, &tests, &f);
+    }
+    g.throughput(Throughput::Elements(elements as u64));
+    for test in &tests {
+        g.bench_function(BenchmarkId::new(test.name(), size), |b| {
+            f(test.as_ref(), Mode::Bench(b))
         });
     }
 }
 
-fn remove(c: &mut Criterion) {
-    let size = 10_000;
-    let mut g = configure(c, "Remove");
+/// Runs every implementation once untimed, aborting if they disagree, and saves their heap usage.
+fn check<T: Payload>(
+    w: &Workload,
+    size: usize,
+    tests: &[Box<dyn Crate<T>>],
+    f: &impl Fn(&dyn Crate<T>, Mode),
+) {
+    let usage = harness::verify(&format!("{}/{}/{}", w.group, T::NAME, size), tests, f);
+    let records: Vec<Record> = usage
+        .into_iter()
+        .map(|(implementation, usage)| Record {
+            workload: w.group.to_string(),
+            payload: T::NAME.to_string(),
+            implementation: implementation.to_string(),
+            size,
+            usage,
+        })
+        .collect();
+    if let Err(e) = memory::save(Path::new(memory::MEMORY_DIR), &records) {
+        eprintln!("warning: memory report not saved: {}", e);
+    }
+}
 
-    // Lookup is being populated with all 10,000 indicies with a non-linear distribution.
+/// All indicies from 0 to size with a non-linear distribution.
+fn shuffled(size: usize) -> Vec<usize> {
     let mut rng = Rand32::new(17534350047697527989);
     let mut lookup = (0..size).collect::<Vec<usize>>();
     for i in 0..size {
         let t = rng.rand_u32() as usize % size;
         lookup.swap(i, t);
     }
-
-    for test in self::tests() {
-        g.bench_function(test.name(), |b| {
-            test.remove(b, &lookup, size);
-        });
-    }
+    lookup
 }
 
-fn get(c: &mut Criterion) {
-    let size = 10_000;
+/// Size indicies drawn uniformly, with repeats.
+fn sampled(size: usize) -> Vec<usize> {
     let mut rng = Rand32::new(17534350047697527989);
     let mut lookup = Vec::with_capacity(size);
     for _ in 0..size {
         lookup.push(rng.rand_u32() as usize % size);
     }
-    let mut g = configure(c, "Get");
-    for test in self::tests() {
-        g.bench_function(test.name(), |b| {
-            test.get(b, &lookup, size);
-        });
-    }
+    lookup
 }
 
-fn iterate(c: &mut Criterion) {
-    let size = 10_000;
-    let mut g = configure(c, "Iter");
-    for test in self::tests() {
-        g.bench_function(test.name(), |b| {
-            test.iterate(b, size);
-        });
+/// Roughly half of the indicies from 0 to size, in order.
+fn halved(size: usize) -> Vec<usize> {
+    let mut rng = Rand32::new(17534350047697527989);
+    let mut lookup = Vec::with_capacity(size);
+    for i in 0..size {
+        if rng.rand_float() < 0.5 {
+            lookup.push(i);
+        }
     }
+    lookup
 }
 
-fn reiterate(c: &mut Criterion) {
-    let size = 10_000;
+/// Whether to keep each of size elements, true for roughly half of them.
+fn kept(size: usize) -> Vec<bool> {
     let mut rng = Rand32::new(17534350047697527989);
-    let mut lookup = Vec::with_capacity(size);
+    (0..size).map(|_| rng.rand_float() >= 0.5).collect()
+}
+
+/// A random half of the indicies as dead, and size lookups where each one hits a dead index with
+/// probability `fraction` and a live index otherwise.
+fn stale(size: usize, fraction: f32) -> (Vec<usize>, Vec<usize>) {
+    let mut rng = Rand32::new(17534350047697527989);
+    let (mut dead, mut live) = (Vec::new(), Vec::new());
     for i in 0..size {
         if rng.rand_float() < 0.5 {
-            lookup.push(i);
+            dead.push(i);
+        } else {
+            live.push(i);
         }
     }
-    let mut g = configure(c, "IterHalf");
-    for test in self::tests() {
-        g.bench_function(test.name(), |b| {
-            test.reiterate(b, &lookup, size);
+    let mut lookup = Vec::with_capacity(size);
+    for _ in 0..size {
+        let from = if rng.rand_float() < fraction {
+            &dead
+        } else {
+            &live
+        };
+        lookup.push(from[rng.rand_u32() as usize % from.len()]);
+    }
+    (dead, lookup)
+}
+
+/// Churn operations for an arena starting at size elements: the warmup followed by the timed
+/// operations. Operations are drawn from `mix`, except that inserts become removes and removes
+/// become inserts as needed to keep the length within 10% of size.
+fn churn(size: usize, mix: Mix) -> (Vec<Op>, Vec<Op>) {
+    let mut rng = Rand32::new(17534350047697527989);
+    let (low, high) = (size - size / 10, size + size / 10);
+    let mut len = size;
+    let mut ops = Vec::with_capacity((harness::CHURN_WARMUP + 1) * size);
+    for _ in 0..(harness::CHURN_WARMUP + 1) * size {
+        let roll = rng.rand_range(0..100);
+        let r = rng.rand_u32();
+        let op = if roll < mix.get {
+            Op::Get(r)
+        } else if (roll < mix.get + mix.insert && len < high) || len <= low {
+            len += 1;
+            Op::Insert
+        } else {
+            len -= 1;
+            Op::Remove(r)
+        };
+        ops.push(op);
+    }
+    let timed = ops.split_off(harness::CHURN_WARMUP * size);
+    (ops, timed)
+}
+
+fn inserts<T: Payload>(c: &mut Criterion) {
+    let w = &harness::INSERT;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        run::<T>(&mut g, w, size, size, |test, m| test.insert(m, size));
+    }
+}
+
+fn inserts_reserved<T: Payload>(c: &mut Criterion) {
+    let w = &harness::INSERT_RESERVED;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        run::<T>(&mut g, w, size, size, |test, m| {
+            test.insert_reserved(m, size)
+        });
+    }
+}
+
+fn reinserts<T: Payload>(c: &mut Criterion) {
+    let w = &harness::INSERT_USED;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        run::<T>(&mut g, w, size, size, |test, m| test.reinsert(m, size));
+    }
+}
+
+fn remove<T: Payload>(c: &mut Criterion) {
+    let w = &harness::REMOVE;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        let lookup = shuffled(size);
+        run::<T>(&mut g, w, size, size, |test, m| {
+            test.remove(m, &lookup, size)
+        });
+    }
+}
+
+fn remove_half<T: Payload>(c: &mut Criterion) {
+    let w = &harness::REMOVE_HALF;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        let keep = kept(size);
+        run::<T>(&mut g, w, size, size, |test, m| {
+            test.remove_half(m, &keep, size)
+        });
+    }
+}
+
+fn retain_half<T: Payload>(c: &mut Criterion) {
+    let w = &harness::RETAIN_HALF;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        let keep = kept(size);
+        run::<T>(&mut g, w, size, size, |test, m| {
+            test.retain_half(m, &keep, size)
+        });
+    }
+}
+
+fn get<T: Payload>(c: &mut Criterion) {
+    let w = &harness::GET;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        let lookup = sampled(size);
+        run::<T>(&mut g, w, size, size, |test, m| test.get(m, &lookup, size));
+    }
+}
+
+fn get_mut<T: Payload>(c: &mut Criterion) {
+    let w = &harness::GET_MUT;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        let lookup = sampled(size);
+        run::<T>(&mut g, w, size, size, |test, m| {
+            test.get_mut(m, &lookup, size)
+        });
+    }
+}
+
+fn get_stale<T: Payload>(c: &mut Criterion) {
+    let w = &harness::GET_STALE;
+    let fraction = harness::stale_fraction();
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        let (dead, lookup) = stale(size, fraction);
+        run::<T>(&mut g, w, size, size, |test, m| {
+            test.get_stale(m, &dead, &lookup, size)
+        });
+    }
+}
+
+fn iterate<T: Payload>(c: &mut Criterion) {
+    let w = &harness::ITER;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        run::<T>(&mut g, w, size, size, |test, m| test.iterate(m, size));
+    }
+}
+
+fn iterate_keys_values<T: Payload>(c: &mut Criterion) {
+    let w = &harness::ITER_KEYS_VALUES;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        run::<T>(&mut g, w, size, size, |test, m| {
+            test.iterate_keys_values(m, size)
+        });
+    }
+}
+
+fn reiterate<T: Payload>(c: &mut Criterion) {
+    let w = &harness::ITER_HALF;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        let lookup = halved(size);
+        let live = size - lookup.len();
+        run::<T>(&mut g, w, size, live, |test, m| {
+            test.reiterate(m, &lookup, size)
+        });
+    }
+}
+
+fn iterate_mut<T: Payload>(c: &mut Criterion) {
+    let w = &harness::ITER_MUT;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        run::<T>(&mut g, w, size, size, |test, m| test.iterate_mut(m, size));
+    }
+}
+
+fn reiterate_mut<T: Payload>(c: &mut Criterion) {
+    let w = &harness::ITER_HALF_MUT;
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        let lookup = halved(size);
+        let live = size - lookup.len();
+        run::<T>(&mut g, w, size, live, |test, m| {
+            test.reiterate_mut(m, &lookup, size)
+        });
+    }
+}
+
+fn churns<T: Payload>(c: &mut Criterion) {
+    let w = &harness::CHURN;
+    let mix = harness::churn_mix();
+    let mut g = group::<T>(c, w);
+    for size in harness::sizes::<T>() {
+        let (warmup, ops) = churn(size, mix);
+        run::<T>(&mut g, w, size, size, |test, m| {
+            test.churn(m, &warmup, &ops, size)
         });
     }
 }
 
-criterion_group!(benches, inserts, reinserts, remove, get, iterate, reiterate);
+fn replay<T: Payload>(c: &mut Criterion) {
+    let trace = harness::replay_trace();
+    // Stale handles only do the same work on implementations that reject them.
+    let w = &if trace.has_stale() {
+        Workload {
+            requires: Capabilities {
+                generational: true,
+                ..Capabilities::NONE
+            },
+            ..harness::REPLAY
+        }
+    } else {
+        harness::REPLAY
+    };
+    let mut g = group::<T>(c, w);
+    run::<T>(&mut g, w, trace.len(), trace.len(), |test, m| {
+        test.replay(m, &trace)
+    });
+}
+
+/// Benchmarks one workload for one payload.
+type Bench = fn(&mut Criterion);
+
+fn workloads<T: Payload>(c: &mut Criterion) {
+    let benches: [(&Workload, Bench); 16] = [
+        (&harness::INSERT, inserts::<T>),
+        (&harness::INSERT_RESERVED, inserts_reserved::<T>),
+        (&harness::INSERT_USED, reinserts::<T>),
+        (&harness::REMOVE, remove::<T>),
+        (&harness::REMOVE_HALF, remove_half::<T>),
+        (&harness::RETAIN_HALF, retain_half::<T>),
+        (&harness::GET, get::<T>),
+        (&harness::GET_MUT, get_mut::<T>),
+        (&harness::GET_STALE, get_stale::<T>),
+        (&harness::ITER, iterate::<T>),
+        (&harness::ITER_KEYS_VALUES, iterate_keys_values::<T>),
+        (&harness::ITER_HALF, reiterate::<T>),
+        (&harness::ITER_MUT, iterate_mut::<T>),
+        (&harness::ITER_HALF_MUT, reiterate_mut::<T>),
+        (&harness::CHURN, churns::<T>),
+        (&harness::REPLAY, replay::<T>),
+    ];
+    let selection = harness::workload_selection();
+    for (w, bench) in benches {
+        if selection.picks_workload(w) {
+            bench(c);
+        }
+    }
+}
+
+fn payloads(c: &mut Criterion) {
+    if let Err(e) = Metadata::current().save(Path::new(metadata::METADATA_FILE)) {
+        eprintln!("warning: run metadata not saved: {}", e);
+    }
+    workloads::<()>(c);
+    workloads::<u32>(c);
+    workloads::<[u8; 64]>(c);
+    workloads::<[u8; 256]>(c);
+    workloads::<[u8; 1024]>(c);
+    workloads::<String>(c);
+}
+
+criterion_group! {
+    name = benches;
+    config = configure();
+    targets = payloads
+}
 criterion_main!(benches);
@@ -0,0 +1,34 @@
+use std::env;
+use std::process::Command;
+
+/// First line of `<tool> --version` for the tool cargo points the build at.
+fn version(tool: &str) -> String {
+    env::var(tool)
+        .ok()
+        .and_then(|path| Command::new(path).arg("--version").output().ok())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .and_then(|text| text.lines().next().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Passes the toolchain and profile this crate is built with on to `genbench::metadata`.
+fn main() {
+    println!("cargo:rustc-env=GENBENCH_BUILD_RUSTC={}", version("RUSTC"));
+    println!("cargo:rustc-env=GENBENCH_BUILD_CARGO={}", version("CARGO"));
+    for var in [
+        "PROFILE",
+        "OPT_LEVEL",
+        "DEBUG",
+        "TARGET",
+        "CARGO_CFG_TARGET_FEATURE",
+    ] {
+        println!(
+            "cargo:rustc-env=GENBENCH_BUILD_{}={}",
+            var,
+            env::var(var).unwrap_or_default()
+        );
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUSTFLAGS");
+    println!("cargo:rerun-if-env-changed=CARGO_ENCODED_RUSTFLAGS");
+}
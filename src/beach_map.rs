@@ -1,123 +1,66 @@
-use crate::Crate;
-use beach_map::BeachMap;
-use criterion::{black_box, BatchSize, Bencher};
+use crate::{Adapter, Arena, Capabilities, Tag};
+use beach_map::{BeachMap, Id};
 
-pub struct CrateBeachMap();
-impl Crate for CrateBeachMap {
-    fn name(&self) -> &'static str {
-        "BeachMap"
+pub type CrateBeachMap<T> = Adapter<BeachMap<T>>;
+
+impl<T: Clone + 'static> Arena<T> for BeachMap<T> {
+    type Key = Id<T>;
+    const NAME: &'static str = "BeachMap";
+    const LINK: &'static str = "https://github.com/leudz/beach_map";
+    const TAGS: &'static [Tag] = &[Tag::Dense];
+    const CAPABILITIES: Capabilities = Capabilities {
+        generational: true,
+        generational_clear: true,
+        iter_mut: true,
+        with_capacity: true,
+        retain: true,
+    };
+
+    fn new() -> Self {
+        BeachMap::new()
+    }
+
+    fn with_capacity(capacity: usize) -> Option<Self> {
+        Some(BeachMap::with_capacity(capacity))
+    }
+
+    fn insert(&mut self, value: T) -> Id<T> {
+        self.insert(value)
+    }
+
+    fn remove(&mut self, key: Id<T>) -> bool {
+        self.remove(key).is_some()
+    }
+
+    fn get(&self, key: Id<T>) -> Option<&T> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: Id<T>) -> Option<&mut T> {
+        self.get_mut(key)
     }
 
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        b.iter_batched(
-            || BeachMap::new(),
-            |mut i: BeachMap<usize>| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.iter()
     }
 
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        b.iter_batched(
-            || {
-                let mut map: BeachMap<usize> = BeachMap::new();
-                let mut keys = Vec::new();
-                for a in 0..size {
-                    keys.push(map.insert(a));
-                }
-                for a in 0..size {
-                    map.remove(keys[a]);
-                }
-                map
-            },
-            |mut i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_with_keys(&self) -> impl Iterator<Item = (Id<T>, &T)> {
+        self.iter_with_id()
     }
 
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        b.iter_batched(
-            || {
-                let mut map: BeachMap<usize> = BeachMap::new();
-                let mut keys = Vec::new();
-                for a in 0..size {
-                    keys.push(map.insert(a));
-                }
-                (map, keys)
-            },
-            |(mut i, keys)| {
-                for a in lookup {
-                    i.remove(keys[*a]);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+        Some(self.iter_mut())
     }
 
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        b.iter_batched(
-            || {
-                let mut map: BeachMap<usize> = BeachMap::new();
-                let mut keys = Vec::new();
-                for a in 0..size {
-                    keys.push(map.insert(a));
-                }
-                (map, keys)
-            },
-            |(i, k)| {
-                for a in 0..size {
-                    black_box(i.get(k[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn len(&self) -> usize {
+        self.len()
     }
 
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        b.iter_batched(
-            || {
-                let mut map: BeachMap<usize> = BeachMap::new();
-                let mut keys = Vec::new();
-                for a in 0..size {
-                    keys.push(map.insert(a));
-                }
-                map
-            },
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn clear(&mut self) {
+        self.clear()
     }
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        b.iter_batched(
-            || {
-                let mut map: BeachMap<usize> = BeachMap::new();
-                let mut keys = Vec::new();
-                for a in 0..size {
-                    keys.push(map.insert(a));
-                }
-                for i in lookup {
-                    map.remove(keys[*i]);
-                }
-                map
-            },
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn retain(&mut self, mut f: impl FnMut(Id<T>, &T) -> bool) {
+        self.retain(|key, value| f(key, value))
     }
 }
@@ -1,110 +1,63 @@
 use crate::extra::bvmap::BvMap;
-use crate::Crate;
-use criterion::{black_box, BatchSize, Bencher};
+use crate::{Adapter, Arena, Capabilities, Tag};
 
-pub struct CrateBvMap();
-impl Crate for CrateBvMap {
-    fn name(&self) -> &'static str {
-        "BvMap"
+pub type CrateBvMap<T> = Adapter<BvMap<usize, T>>;
+
+impl<T: Clone + 'static> Arena<T> for BvMap<usize, T> {
+    type Key = usize;
+    const NAME: &'static str = "BvMap";
+    const LINK: &'static str = "https://github.com/spersson/bvmap/";
+    const TAGS: &'static [Tag] = &[Tag::Bitset];
+    const CAPABILITIES: Capabilities = Capabilities {
+        generational: false,
+        generational_clear: false,
+        iter_mut: true,
+        with_capacity: false,
+        retain: false,
+    };
+
+    fn new() -> Self {
+        BvMap::new()
+    }
+
+    fn with_capacity(_capacity: usize) -> Option<Self> {
+        // BvMap cannot reserve capacity up front.
+        None
+    }
+
+    fn insert(&mut self, value: T) -> usize {
+        self.insert(value)
+    }
+
+    fn remove(&mut self, key: usize) -> bool {
+        self.remove(key).is_some()
+    }
+
+    fn get(&self, key: usize) -> Option<&T> {
+        self.get(key)
     }
 
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: BvMap<usize, usize> = BvMap::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn get_mut(&mut self, key: usize) -> Option<&mut T> {
+        self.get_mut(key)
     }
 
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: BvMap<usize, usize> = BvMap::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        for a in 0..size {
-            map.remove(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.iter()
     }
 
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: BvMap<usize, usize> = BvMap::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.remove(*a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_with_keys(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.iter_with_keys()
     }
 
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: BvMap<usize, usize> = BvMap::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(lookup[a]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+        Some(self.iter_mut())
     }
 
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: BvMap<usize, usize> = BvMap::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn len(&self) -> usize {
+        self.len()
     }
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: BvMap<usize, usize> = BvMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for i in lookup {
-            map.remove(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn clear(&mut self) {
+        self.clear()
     }
 }
@@ -0,0 +1,287 @@
+use std::fmt::Write;
+
+/// Colors given to series in order, repeating once they run out.
+pub const PALETTE: [&str; 20] = [
+    "#1f77b4", "#aec7e8", "#ff7f0e", "#ffbb78", "#2ca02c", "#98df8a", "#d62728", "#ff9896",
+    "#9467bd", "#c5b0d5", "#8c564b", "#c49c94", "#e377c2", "#f7b6d2", "#7f7f7f", "#c7c7c7",
+    "#bcbd22", "#dbdb8d", "#17becf", "#9edae5",
+];
+
+const HEIGHT: f64 = 480.0;
+const TOP: f64 = 40.0;
+const BOTTOM: f64 = 40.0;
+const LEFT: f64 = 70.0;
+const LEGEND: f64 = 190.0;
+const BAR: f64 = 6.0;
+const GAP: f64 = 24.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Scale {
+    Linear,
+    Log,
+}
+
+/// One bar per group, all in the same color.
+pub struct Series {
+    pub name: String,
+    pub color: &'static str,
+    /// Indexed like `Chart::groups`; `None` leaves a gap.
+    pub values: Vec<Option<f64>>,
+}
+
+/// A grouped bar chart: groups along the x axis, one bar per series within each group.
+pub struct Chart {
+    pub title: String,
+    /// Label of the y axis.
+    pub unit: String,
+    pub scale: Scale,
+    pub groups: Vec<String>,
+    pub series: Vec<Series>,
+}
+
+impl Chart {
+    /// Renders the chart as a standalone SVG document. Values that cannot be drawn on the scale,
+    /// such as zero on a log axis, are left out.
+    pub fn to_svg(&self) -> String {
+        let plot_width = self.groups.len() as f64 * (self.series.len() as f64 * BAR + GAP);
+        let width = LEFT + plot_width + LEGEND;
+        let plot_height = HEIGHT - TOP - BOTTOM;
+        let axis = Axis::new(self.scale, self.values());
+        let y = |value: f64| TOP + plot_height * (1.0 - axis.fraction(value));
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="12">"#,
+            w = width,
+            h = HEIGHT
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect width="{}" height="{}" fill="white"/>"#,
+            width, HEIGHT
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="24" font-size="16" text-anchor="middle">{}</text>"#,
+            LEFT + plot_width / 2.0,
+            escape(&self.title)
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text transform="translate(16 {}) rotate(-90)" text-anchor="middle">{}</text>"#,
+            TOP + plot_height / 2.0,
+            escape(&self.unit)
+        );
+
+        for tick in axis.ticks() {
+            let _ = writeln!(
+                svg,
+                r##"<line x1="{x1}" x2="{x2}" y1="{y:.1}" y2="{y:.1}" stroke="#ddd"/><text x="{tx}" y="{y:.1}" dy="4" text-anchor="end">{label}</text>"##,
+                x1 = LEFT,
+                x2 = LEFT + plot_width,
+                y = y(tick),
+                tx = LEFT - 6.0,
+                label = label(tick)
+            );
+        }
+
+        for (g, group) in self.groups.iter().enumerate() {
+            let start = LEFT + GAP / 2.0 + g as f64 * (self.series.len() as f64 * BAR + GAP);
+            for (s, series) in self.series.iter().enumerate() {
+                let value = match series.values.get(g).copied().flatten() {
+                    Some(value) if axis.draws(value) => value,
+                    _ => continue,
+                };
+                let top = y(value);
+                let _ = writeln!(
+                    svg,
+                    r#"<rect x="{:.1}" y="{:.1}" width="{}" height="{:.1}" fill="{}"><title>{}: {}</title></rect>"#,
+                    start + s as f64 * BAR,
+                    top,
+                    BAR,
+                    TOP + plot_height - top,
+                    series.color,
+                    escape(&series.name),
+                    label(value)
+                );
+            }
+            let _ = writeln!(
+                svg,
+                r#"<text x="{:.1}" y="{}" text-anchor="middle">{}</text>"#,
+                start + self.series.len() as f64 * BAR / 2.0,
+                HEIGHT - BOTTOM + 18.0,
+                escape(group)
+            );
+        }
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{x}" x2="{x}" y1="{}" y2="{b}" stroke="black"/><line x1="{x}" x2="{}" y1="{b}" y2="{b}" stroke="black"/>"#,
+            TOP,
+            LEFT + plot_width,
+            x = LEFT,
+            b = TOP + plot_height
+        );
+
+        let legend = LEFT + plot_width + 16.0;
+        for (s, series) in self.series.iter().enumerate() {
+            let top = TOP + s as f64 * 18.0;
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="12" height="12" fill="{}"/><text x="{}" y="{}">{}</text>"#,
+                legend,
+                top,
+                series.color,
+                legend + 18.0,
+                top + 10.0,
+                escape(&series.name)
+            );
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn values(&self) -> impl Iterator<Item = f64> + '_ {
+        self.series
+            .iter()
+            .flat_map(|series| series.values.iter().flatten().copied())
+    }
+}
+
+/// Maps values onto the height of the plot.
+struct Axis {
+    scale: Scale,
+    min: f64,
+    max: f64,
+}
+
+impl Axis {
+    /// Linear axes start at zero and end at a round number. Log axes span whole decades.
+    fn new(scale: Scale, values: impl Iterator<Item = f64>) -> Axis {
+        match scale {
+            Scale::Linear => {
+                let max = values.fold(0.0, f64::max);
+                Axis {
+                    scale,
+                    min: 0.0,
+                    max: round_up(max),
+                }
+            }
+            Scale::Log => {
+                let (low, high) = values
+                    .filter(|&v| v > 0.0)
+                    .fold((f64::INFINITY, 0.0f64), |(low, high), v| {
+                        (low.min(v), high.max(v))
+                    });
+                if high == 0.0 {
+                    return Axis {
+                        scale,
+                        min: 1.0,
+                        max: 10.0,
+                    };
+                }
+                let min = 10f64.powf(low.log10().floor());
+                let max = 10f64.powf(high.log10().ceil()).max(min * 10.0);
+                Axis { scale, min, max }
+            }
+        }
+    }
+
+    fn draws(&self, value: f64) -> bool {
+        value.is_finite() && (self.scale == Scale::Linear || value > 0.0)
+    }
+
+    /// Position of `value` from the bottom (0) to the top (1) of the plot.
+    fn fraction(&self, value: f64) -> f64 {
+        match self.scale {
+            Scale::Linear => value / self.max,
+            Scale::Log => (value / self.min).log10() / (self.max / self.min).log10(),
+        }
+    }
+
+    fn ticks(&self) -> Vec<f64> {
+        match self.scale {
+            Scale::Linear => (0..=5).map(|i| self.max * i as f64 / 5.0).collect(),
+            Scale::Log => {
+                let decades = (self.max / self.min).log10().round() as i32;
+                (0..=decades).map(|i| self.min * 10f64.powi(i)).collect()
+            }
+        }
+    }
+}
+
+/// Smallest 1, 2 or 5 times a power of ten that is at least `value`.
+fn round_up(value: f64) -> f64 {
+    if value <= 0.0 || !value.is_finite() {
+        return 1.0;
+    }
+    let decade = 10f64.powf(value.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|step| step * decade)
+        .find(|&max| max >= value)
+        .unwrap_or(10.0 * decade)
+}
+
+fn label(value: f64) -> String {
+    if value == 0.0 || value >= 100.0 {
+        format!("{:.0}", value)
+    } else if value >= 1.0 {
+        format!("{:.1}", value)
+    } else {
+        format!("{:.3}", value)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn axis_bounds() {
+        let linear = Axis::new(Scale::Linear, [3.0, 17.0].into_iter());
+        assert_eq!((linear.min, linear.max), (0.0, 20.0));
+        assert_eq!(linear.fraction(10.0), 0.5);
+
+        let log = Axis::new(Scale::Log, [0.0, 0.5, 30.0].into_iter());
+        assert_eq!((log.min, log.max), (0.1, 100.0));
+        assert_eq!(log.ticks().len(), 4);
+        assert!(!log.draws(0.0));
+    }
+
+    #[test]
+    fn svg_skips_missing_bars() {
+        let chart = Chart {
+            title: "Get/u32 <10000>".to_string(),
+            unit: "ns per element".to_string(),
+            scale: Scale::Linear,
+            groups: vec!["Get".to_string(), "Insert".to_string()],
+            series: vec![
+                Series {
+                    name: "SlotMap".to_string(),
+                    color: PALETTE[0],
+                    values: vec![Some(4.0), Some(8.0)],
+                },
+                Series {
+                    name: "Slab".to_string(),
+                    color: PALETTE[1],
+                    values: vec![None, Some(2.0)],
+                },
+            ],
+        };
+        let svg = chart.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("Get/u32 &lt;10000&gt;"));
+        assert_eq!(svg.matches("<title>").count(), 3);
+    }
+}
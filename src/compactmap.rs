@@ -1,113 +1,63 @@
-use crate::Crate;
+use crate::{Adapter, Arena, Capabilities, Tag};
 use compactmap::CompactMap;
-use criterion::{black_box, BatchSize, Bencher};
 
-pub struct CrateCompactMap();
-impl Crate for CrateCompactMap {
-    fn name(&self) -> &'static str {
-        "CompactMap"
+pub type CrateCompactMap<T> = Adapter<CompactMap<T>>;
+
+impl<T: Clone + 'static> Arena<T> for CompactMap<T> {
+    type Key = usize;
+    const NAME: &'static str = "CompactMap";
+    const LINK: &'static str = "https://github.com/vi/compactmap";
+    const TAGS: &'static [Tag] = &[Tag::UnsafeFree];
+    const CAPABILITIES: Capabilities = Capabilities {
+        generational: false,
+        generational_clear: false,
+        iter_mut: true,
+        with_capacity: true,
+        retain: false,
+    };
+
+    fn new() -> Self {
+        CompactMap::new()
+    }
+
+    fn with_capacity(capacity: usize) -> Option<Self> {
+        Some(CompactMap::with_capacity(capacity))
+    }
+
+    fn insert(&mut self, value: T) -> usize {
+        self.insert(value)
+    }
+
+    fn remove(&mut self, key: usize) -> bool {
+        self.remove(key).is_some()
+    }
+
+    fn get(&self, key: usize) -> Option<&T> {
+        self.get(key)
     }
 
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: CompactMap<usize> = CompactMap::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn get_mut(&mut self, key: usize) -> Option<&mut T> {
+        self.get_mut(key)
     }
 
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: CompactMap<usize> = CompactMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for a in 0..size {
-            map.remove(keys[a]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.values()
     }
 
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: CompactMap<usize> = CompactMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.remove(keys[*a]);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_with_keys(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.iter()
     }
 
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: CompactMap<usize> = CompactMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(keys[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+        Some(self.values_mut())
     }
 
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: CompactMap<usize> = CompactMap::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn len(&self) -> usize {
+        // CompactMap does not track its length.
+        self.len_slow()
     }
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: CompactMap<usize> = CompactMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for i in lookup {
-            map.remove(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn clear(&mut self) {
+        self.clear()
     }
 }
@@ -0,0 +1,266 @@
+use crate::export::{Export, Row};
+use std::fmt::Write;
+
+/// Whether two results differ by more than run-to-run noise, judged by their mean's 95%
+/// confidence intervals not overlapping.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Significance {
+    Improved,
+    Regressed,
+    Unchanged,
+}
+
+/// The same benchmark in a baseline and a current result set. Times are per element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delta {
+    pub implementation: String,
+    pub workload: String,
+    pub payload: String,
+    pub size: usize,
+    pub baseline: f64,
+    pub current: f64,
+    pub significance: Significance,
+}
+
+impl Delta {
+    /// Change of the mean relative to the baseline, in percent. Infinite for any slowdown from a
+    /// baseline of zero, which a zero sized payload can estimate.
+    pub fn percent(&self) -> f64 {
+        if self.baseline <= 0.0 {
+            return if self.current > self.baseline {
+                f64::INFINITY
+            } else {
+                0.0
+            };
+        }
+        (self.current / self.baseline - 1.0) * 100.0
+    }
+
+    /// A significant slowdown of more than `threshold` percent, or any slowdown from a baseline of
+    /// zero.
+    pub fn regressed(&self, threshold: f64) -> bool {
+        if self.baseline <= 0.0 {
+            return self.current > self.baseline;
+        }
+        self.significance == Significance::Regressed && self.percent() > threshold
+    }
+}
+
+/// Every benchmark of a baseline matched against a current result set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Comparison {
+    pub deltas: Vec<Delta>,
+    /// Benchmarks only the baseline has, as `workload/payload/implementation/size`.
+    pub removed: Vec<String>,
+    /// Benchmarks only the current set has.
+    pub added: Vec<String>,
+}
+
+/// Matches results by implementation, workload, payload and size. Times are compared per element,
+/// so sets whose throughput differs for the same size still line up.
+pub fn compare(baseline: &Export, current: &Export) -> Comparison {
+    let same = |a: &Row, b: &Row| {
+        a.implementation == b.implementation
+            && a.workload == b.workload
+            && a.payload == b.payload
+            && a.size == b.size
+    };
+    let mut comparison = Comparison::default();
+    for b in &baseline.results {
+        let c = match current.results.iter().find(|c| same(b, c)) {
+            Some(c) => c,
+            None => {
+                comparison.removed.push(id(b));
+                continue;
+            }
+        };
+        let per_element = |r: &Row, ns: f64| ns / r.elements.max(1) as f64;
+        let significance = if per_element(c, c.mean.lower) > per_element(b, b.mean.upper) {
+            Significance::Regressed
+        } else if per_element(c, c.mean.upper) < per_element(b, b.mean.lower) {
+            Significance::Improved
+        } else {
+            Significance::Unchanged
+        };
+        comparison.deltas.push(Delta {
+            implementation: b.implementation.clone(),
+            workload: b.workload.clone(),
+            payload: b.payload.clone(),
+            size: b.size,
+            baseline: per_element(b, b.mean.point),
+            current: per_element(c, c.mean.point),
+            significance,
+        });
+    }
+    comparison.added = current
+        .results
+        .iter()
+        .filter(|c| !baseline.results.iter().any(|b| same(b, c)))
+        .map(id)
+        .collect();
+    comparison
+}
+
+/// Refuses to compare runs made with different `GENBENCH_PROFILE`s, whose sample counts and sizes
+/// differ. Runs that did not record their profile are let through.
+pub fn check_profiles(baseline: &Export, current: &Export) -> Result<(), String> {
+    let (b, c) = (
+        &baseline.metadata.bench_profile,
+        &current.metadata.bench_profile,
+    );
+    if b.is_empty() || c.is_empty() || b == c {
+        return Ok(());
+    }
+    Err(format!(
+        "the baseline ran with the {} benchmark profile and the current set with {}",
+        b, c
+    ))
+}
+
+impl Comparison {
+    /// A Markdown table of every delta. Significant changes are flagged, and regressions beyond
+    /// `threshold` percent are in bold.
+    pub fn to_markdown(&self, threshold: f64) -> String {
+        let mut text = "| Workload | Payload | Size | Implementation | Baseline ns/elem | \
+                        Current ns/elem | Delta | Significance |\n\
+                        |---|---|---:|---|---:|---:|---:|---|\n"
+            .to_string();
+        for d in &self.deltas {
+            let flag = match d.significance {
+                Significance::Regressed if d.regressed(threshold) => "**regressed**",
+                Significance::Regressed => "regressed",
+                Significance::Improved => "improved",
+                Significance::Unchanged => "",
+            };
+            let _ = writeln!(
+                text,
+                "| {} | {} | {} | {} | {:.2} | {:.2} | {:+.1}% | {} |",
+                d.workload,
+                d.payload,
+                d.size,
+                d.implementation,
+                d.baseline,
+                d.current,
+                d.percent(),
+                flag
+            );
+        }
+        for (label, ids) in [
+            ("Only in baseline", &self.removed),
+            ("Only in current", &self.added),
+        ] {
+            if !ids.is_empty() {
+                let _ = write!(text, "\n{}: {}\n", label, ids.join(", "));
+            }
+        }
+        text
+    }
+}
+
+fn id(r: &Row) -> String {
+    format!(
+        "{}/{}/{}/{}",
+        r.workload, r.payload, r.implementation, r.size
+    )
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{Interval, SCHEMA};
+    use crate::metadata::Metadata;
+
+    fn row(implementation: &str, size: usize, lower: f64, upper: f64) -> Row {
+        let mean = Interval {
+            point: (lower + upper) / 2.0,
+            lower,
+            upper,
+        };
+        Row {
+            implementation: implementation.to_string(),
+            workload: "Get".to_string(),
+            payload: "u32".to_string(),
+            size,
+            elements: size as u64,
+            samples: 200,
+            mean,
+            median: mean,
+        }
+    }
+
+    fn set(results: Vec<Row>) -> Export {
+        Export {
+            schema: SCHEMA,
+            metadata: Metadata::default(),
+            results,
+        }
+    }
+
+    #[test]
+    fn flags_significant_changes() {
+        let baseline = set(vec![
+            row("SlotMap", 100, 900.0, 1100.0),
+            row("Slab", 100, 900.0, 1100.0),
+            row("Stash", 100, 900.0, 1100.0),
+            row("Stash", 1000, 900.0, 1100.0),
+        ]);
+        let current = set(vec![
+            row("SlotMap", 100, 1150.0, 1250.0),
+            row("Slab", 100, 600.0, 800.0),
+            row("Stash", 100, 1000.0, 1200.0),
+            row("IdVec", 100, 1000.0, 1200.0),
+        ]);
+        let comparison = compare(&baseline, &current);
+        let flags: Vec<Significance> = comparison.deltas.iter().map(|d| d.significance).collect();
+        assert_eq!(
+            flags,
+            [
+                Significance::Regressed,
+                Significance::Improved,
+                Significance::Unchanged
+            ]
+        );
+        assert!((comparison.deltas[0].percent() - 20.0).abs() < 1e-9);
+        assert!(comparison.deltas[0].regressed(10.0));
+        assert!(!comparison.deltas[0].regressed(25.0));
+        assert!(!comparison.deltas[2].regressed(0.0));
+        assert_eq!(comparison.removed, ["Get/u32/Stash/1000"]);
+        assert_eq!(comparison.added, ["Get/u32/IdVec/100"]);
+    }
+
+    #[test]
+    fn zero_baseline() {
+        let baseline = set(vec![
+            row("Slab", 100, 0.0, 0.0),
+            row("Stash", 100, 0.0, 0.0),
+        ]);
+        let current = set(vec![
+            row("Slab", 100, 0.0, 2.0),
+            row("Stash", 100, 0.0, 0.0),
+        ]);
+        let comparison = compare(&baseline, &current);
+        let slower = &comparison.deltas[0];
+        assert_eq!(slower.percent(), f64::INFINITY);
+        assert!(slower.regressed(1000.0));
+        let same = &comparison.deltas[1];
+        assert_eq!(same.percent(), 0.0);
+        assert!(!same.regressed(0.0));
+    }
+
+    #[test]
+    fn refuses_other_profiles() {
+        let with = |profile: &str| {
+            let mut export = set(vec![row("SlotMap", 100, 900.0, 1100.0)]);
+            export.metadata.bench_profile = profile.to_string();
+            export
+        };
+        assert!(check_profiles(&with("standard"), &with("standard")).is_ok());
+        assert!(check_profiles(&with(""), &with("quick")).is_ok());
+        let error = check_profiles(&with("standard"), &with("quick")).unwrap_err();
+        assert!(error.contains("standard") && error.contains("quick"));
+    }
+}
@@ -0,0 +1,330 @@
+use crate::{Arena, Payload};
+
+/// Number of elements each check works with.
+const SIZE: usize = 100;
+
+macro_rules! ensure {
+    ($cond:expr, $($arg:tt)*) => {
+        if !$cond {
+            return Err(format!($($arg)*));
+        }
+    };
+}
+
+/// Runs every check that applies to `A` given its declared capabilities.
+pub fn check<T: Payload, A: Arena<T>>() -> Result<(), String> {
+    round_trip::<T, A>()?;
+    stale_after_remove::<T, A>()?;
+    reuse_after_remove::<T, A>()?;
+    len_tracking::<T, A>()?;
+    iteration::<T, A>()?;
+    clear::<T, A>()?;
+    reserved::<T, A>()?;
+    retain::<T, A>()?;
+    Ok(())
+}
+
+fn fill<T: Payload, A: Arena<T>>(size: usize) -> (A, Vec<A::Key>) {
+    let mut map = A::new();
+    let keys = (0..size).map(|a| map.insert(T::new(a))).collect();
+    (map, keys)
+}
+
+fn holds<T: Payload, A: Arena<T>>(map: &A, key: A::Key, i: usize) -> bool {
+    map.get(key).map(T::checksum) == Some(T::new(i).checksum())
+}
+
+/// Sums the checksums of the live values, or of everything iteration yields.
+fn expected<T: Payload>(live: impl Iterator<Item = usize>) -> (usize, u64) {
+    live.fold((0, 0), |(n, sum), i| (n + 1, sum + T::new(i).checksum()))
+}
+
+fn yielded<'a, T: Payload>(values: impl Iterator<Item = &'a T>) -> (usize, u64) {
+    values.fold((0, 0), |(n, sum), v| (n + 1, sum + v.checksum()))
+}
+
+/// Every inserted value can be read back through its key.
+pub fn round_trip<T: Payload, A: Arena<T>>() -> Result<(), String> {
+    let (mut map, keys) = fill::<T, A>(SIZE);
+    for (i, key) in keys.iter().enumerate() {
+        ensure!(
+            holds(&map, *key, i),
+            "get({}) did not return the inserted value",
+            i
+        );
+        let value = map.get_mut(*key).map(|v| v.checksum());
+        ensure!(
+            value == Some(T::new(i).checksum()),
+            "get_mut({}) did not return the inserted value",
+            i
+        );
+    }
+    Ok(())
+}
+
+/// A removed key is rejected by get, get_mut and a second remove.
+pub fn stale_after_remove<T: Payload, A: Arena<T>>() -> Result<(), String> {
+    let (mut map, keys) = fill::<T, A>(SIZE);
+    for (i, key) in keys.iter().enumerate().step_by(2) {
+        ensure!(map.remove(*key), "remove({}) of a live key failed", i);
+        ensure!(!map.remove(*key), "remove({}) accepted a removed key", i);
+        ensure!(map.get(*key).is_none(), "get({}) accepted a removed key", i);
+        ensure!(
+            map.get_mut(*key).is_none(),
+            "get_mut({}) accepted a removed key",
+            i
+        );
+    }
+    for (i, key) in keys.iter().enumerate().skip(1).step_by(2) {
+        ensure!(
+            holds(&map, *key, i),
+            "get({}) lost a value after unrelated removes",
+            i
+        );
+    }
+    Ok(())
+}
+
+/// Freed slots can be filled again, and generational implementations reject the old keys.
+pub fn reuse_after_remove<T: Payload, A: Arena<T>>() -> Result<(), String> {
+    let (mut map, keys) = fill::<T, A>(SIZE);
+    for key in &keys {
+        map.remove(*key);
+    }
+    let fresh: Vec<A::Key> = (SIZE..2 * SIZE).map(|i| map.insert(T::new(i))).collect();
+    for (i, key) in fresh.iter().enumerate() {
+        ensure!(
+            holds(&map, *key, SIZE + i),
+            "get({}) failed after reusing slots",
+            SIZE + i
+        );
+    }
+    if A::CAPABILITIES.generational {
+        for (i, key) in keys.iter().enumerate() {
+            ensure!(
+                map.get(*key).is_none(),
+                "get({}) accepted a key to a reused slot",
+                i
+            );
+            ensure!(
+                !map.remove(*key),
+                "remove({}) accepted a key to a reused slot",
+                i
+            );
+        }
+    }
+    ensure!(
+        map.len() == SIZE,
+        "len() is {} after reusing {} slots",
+        map.len(),
+        SIZE
+    );
+    Ok(())
+}
+
+/// len follows inserts and removes, including failed removes.
+pub fn len_tracking<T: Payload, A: Arena<T>>() -> Result<(), String> {
+    let mut map = A::new();
+    ensure!(map.is_empty(), "a new arena is not empty");
+    let mut keys = Vec::new();
+    for i in 0..SIZE {
+        keys.push(map.insert(T::new(i)));
+        ensure!(
+            map.len() == i + 1,
+            "len() is {} after {} inserts",
+            map.len(),
+            i + 1
+        );
+    }
+    for (i, key) in keys.iter().enumerate() {
+        map.remove(*key);
+        map.remove(*key);
+        ensure!(
+            map.len() == SIZE - i - 1,
+            "len() is {} after {} removes",
+            map.len(),
+            i + 1
+        );
+    }
+    ensure!(
+        map.is_empty(),
+        "the arena is not empty after removing everything"
+    );
+    Ok(())
+}
+
+/// Iteration yields exactly the live values, once each.
+pub fn iteration<T: Payload, A: Arena<T>>() -> Result<(), String> {
+    let (mut map, keys) = fill::<T, A>(SIZE);
+    for key in keys.iter().step_by(3) {
+        map.remove(*key);
+    }
+    let live = expected::<T>((0..SIZE).filter(|i| i % 3 != 0));
+    ensure!(
+        yielded(map.iter()) == live,
+        "iter() did not yield exactly the live values"
+    );
+    ensure!(
+        yielded(map.iter_with_keys().map(|(_, v)| v)) == live,
+        "iter_with_keys() did not yield exactly the live values"
+    );
+    for (key, value) in map.iter_with_keys() {
+        ensure!(
+            map.get(key).map(T::checksum) == Some(value.checksum()),
+            "iter_with_keys() yielded a key that does not get its value"
+        );
+    }
+    if A::CAPABILITIES.iter_mut {
+        let values = map.iter_mut().map(|iter| yielded(iter.map(|v| &*v)));
+        ensure!(
+            values == Some(live),
+            "iter_mut() did not yield exactly the live values"
+        );
+    } else {
+        ensure!(
+            map.iter_mut().is_none(),
+            "iter_mut() is supported but not declared"
+        );
+    }
+    Ok(())
+}
+
+/// clear empties the arena, and generational implementations keep rejecting the old keys.
+pub fn clear<T: Payload, A: Arena<T>>() -> Result<(), String> {
+    let (mut map, keys) = fill::<T, A>(SIZE);
+    map.clear();
+    ensure!(map.is_empty(), "len() is {} after clear", map.len());
+    ensure!(
+        map.iter().next().is_none(),
+        "iter() yielded a value after clear"
+    );
+    for (i, key) in keys.iter().enumerate() {
+        ensure!(
+            map.get(*key).is_none(),
+            "get({}) accepted a key after clear",
+            i
+        );
+    }
+    let fresh: Vec<A::Key> = (SIZE..2 * SIZE).map(|i| map.insert(T::new(i))).collect();
+    for (i, key) in fresh.iter().enumerate() {
+        ensure!(
+            holds(&map, *key, SIZE + i),
+            "get({}) failed after clear",
+            SIZE + i
+        );
+    }
+    if A::CAPABILITIES.generational_clear {
+        for (i, key) in keys.iter().enumerate() {
+            ensure!(
+                map.get(*key).is_none(),
+                "get({}) accepted a key from before clear",
+                i
+            );
+        }
+    }
+    Ok(())
+}
+
+/// with_capacity is available exactly when declared, and a reserved arena works like a new one.
+pub fn reserved<T: Payload, A: Arena<T>>() -> Result<(), String> {
+    let map = A::with_capacity(SIZE);
+    if !A::CAPABILITIES.with_capacity {
+        ensure!(
+            map.is_none(),
+            "with_capacity() is supported but not declared"
+        );
+        return Ok(());
+    }
+    let mut map = match map {
+        Some(map) => map,
+        None => return Err("with_capacity() is declared but not supported".to_string()),
+    };
+    ensure!(map.is_empty(), "a reserved arena is not empty");
+    let keys: Vec<A::Key> = (0..SIZE).map(|i| map.insert(T::new(i))).collect();
+    for (i, key) in keys.iter().enumerate() {
+        ensure!(
+            holds(&map, *key, i),
+            "get({}) failed in a reserved arena",
+            i
+        );
+    }
+    Ok(())
+}
+
+/// retain visits every live value once and keeps exactly the ones it was told to.
+pub fn retain<T: Payload, A: Arena<T>>() -> Result<(), String> {
+    let (mut map, _) = fill::<T, A>(SIZE);
+    let (mut kept, mut dropped) = (Vec::new(), Vec::new());
+    let mut visits = 0;
+    map.retain(|key, value| {
+        visits += 1;
+        let keep = visits % 2 == 0;
+        if keep {
+            kept.push((key, value.checksum()));
+        } else {
+            dropped.push(key);
+        }
+        keep
+    });
+    ensure!(
+        visits == SIZE,
+        "retain() visited {} of {} values",
+        visits,
+        SIZE
+    );
+    ensure!(
+        map.len() == kept.len(),
+        "len() is {} after retain() kept {}",
+        map.len(),
+        kept.len()
+    );
+    for (key, checksum) in kept {
+        ensure!(
+            map.get(key).map(T::checksum) == Some(checksum),
+            "retain() lost a value it was told to keep"
+        );
+    }
+    for key in dropped {
+        ensure!(
+            map.get(key).is_none(),
+            "retain() kept a value it was told to drop"
+        );
+    }
+    Ok(())
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use crate::Payload;
+
+    fn conforms<T: Payload>() {
+        let failures: Vec<String> = crate::crates::<T>()
+            .iter()
+            .filter_map(|test| {
+                test.conformance()
+                    .err()
+                    .map(|e| format!("{}: {}", test.name(), e))
+            })
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn conforms_zst() {
+        conforms::<()>();
+    }
+
+    #[test]
+    fn conforms_u32() {
+        conforms::<u32>();
+    }
+
+    #[test]
+    fn conforms_string() {
+        conforms::<String>();
+    }
+}
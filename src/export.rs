@@ -0,0 +1,199 @@
+use crate::metadata::Metadata;
+use crate::results::{Estimate, Measurement};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Bumped whenever a field of the exported JSON is renamed, removed or changes meaning. Adding a
+/// field does not change the schema.
+pub const SCHEMA: u32 = 1;
+
+/// Every benchmark result of a run, flattened for consumers other than criterion.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Export {
+    pub schema: u32,
+    /// Host, build and dependency versions of the run, as fields of the export itself.
+    #[serde(flatten)]
+    pub metadata: Metadata,
+    pub results: Vec<Row>,
+}
+
+/// One implementation on one workload, payload and size. Times are nanoseconds per iteration;
+/// one iteration touches `elements` elements.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Row {
+    pub implementation: String,
+    pub workload: String,
+    pub payload: String,
+    pub size: usize,
+    pub elements: u64,
+    pub samples: usize,
+    pub mean: Interval,
+    pub median: Interval,
+}
+
+/// A point estimate and the bounds of its 95% confidence interval.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Interval {
+    pub point: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl From<Estimate> for Interval {
+    fn from(e: Estimate) -> Self {
+        Interval {
+            point: e.point,
+            lower: e.lower,
+            upper: e.upper,
+        }
+    }
+}
+
+impl Export {
+    pub fn new(metadata: Metadata, measurements: &[Measurement]) -> Export {
+        Export {
+            schema: SCHEMA,
+            metadata,
+            results: measurements
+                .iter()
+                .map(|m| Row {
+                    implementation: m.implementation.clone(),
+                    workload: m.workload.clone(),
+                    payload: m.payload.clone(),
+                    size: m.size,
+                    elements: m.elements,
+                    samples: m.samples,
+                    mean: m.mean.into(),
+                    median: m.median.into(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        // Only strings, integers and floats, none of which can fail to serialize.
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// One line per result, with the host and build repeated on every line so each row stands
+    /// alone.
+    pub fn to_csv(&self) -> String {
+        let mut csv = "implementation,workload,payload,size,elements,samples,\
+                       mean_ns,mean_lower_ns,mean_upper_ns,median_ns,median_lower_ns,median_upper_ns,\
+                       hostname,os,arch,cpus,cpu_model,rustc,profile,opt_level,bench_profile\n"
+            .to_string();
+        let (host, build) = (&self.metadata.host, &self.metadata.build);
+        let host = [
+            field(host.hostname.as_deref().unwrap_or("")),
+            field(&host.os),
+            field(&host.arch),
+            host.cpus.to_string(),
+            field(host.cpu_model.as_deref().unwrap_or("")),
+            field(&build.rustc),
+            field(&build.profile),
+            field(&build.opt_level),
+            field(&self.metadata.bench_profile),
+        ]
+        .join(",");
+        for r in &self.results {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                field(&r.implementation),
+                field(&r.workload),
+                field(&r.payload),
+                r.size,
+                r.elements,
+                r.samples,
+                r.mean.point,
+                r.mean.lower,
+                r.mean.upper,
+                r.median.point,
+                r.median.lower,
+                r.median.upper,
+                host
+            );
+        }
+        csv
+    }
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::Host;
+    use crate::metadata::Build;
+
+    fn export() -> Export {
+        let estimate = Estimate {
+            point: 1000.0,
+            lower: 900.0,
+            upper: 1100.5,
+        };
+        let host = Host {
+            hostname: Some("bench".to_string()),
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            cpus: 32,
+            cpu_model: Some("Vendor \"X\", 16-Core".to_string()),
+            ..Host::default()
+        };
+        let metadata = Metadata {
+            host,
+            build: Build {
+                rustc: "rustc 1.95.0".to_string(),
+                profile: "release".to_string(),
+                opt_level: "3".to_string(),
+                ..Build::default()
+            },
+            dependencies: [("slotmap".to_string(), "1.1.1".to_string())].into(),
+            bench_profile: "standard".to_string(),
+        };
+        Export::new(
+            metadata,
+            &[Measurement {
+                workload: "IterHalf".to_string(),
+                payload: "u32".to_string(),
+                implementation: "SlotMap".to_string(),
+                size: 1000,
+                elements: 500,
+                mean: estimate,
+                median: estimate,
+                samples: 200,
+            }],
+        )
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let export = export();
+        let json = export.to_json();
+        assert!(json.contains("\"schema\": 1"));
+        assert_eq!(serde_json::from_str::<Export>(&json).unwrap(), export);
+    }
+
+    #[test]
+    fn csv_rows() {
+        let csv = export().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[1],
+            "SlotMap,IterHalf,u32,1000,500,200,1000,900,1100.5,1000,900,1100.5,\
+             bench,linux,x86_64,32,\"Vendor \"\"X\"\", 16-Core\",rustc 1.95.0,release,3,standard"
+        );
+    }
+}
@@ -42,6 +42,7 @@ union Slot<V> {
 #[derive(Default)]
 pub struct BvMap<K, V> {
     next_free: usize,
+    len: usize,
     bitvec: SmallBitVec,
     vec: Vec<Slot<V>>,
     marker: PhantomData<fn(K) -> K>,
@@ -51,6 +52,7 @@ impl<K: Into<usize> + From<usize>, V> BvMap<K, V> {
     pub fn new() -> BvMap<K, V> {
         BvMap {
             next_free: 0,
+            len: 0,
             bitvec: SmallBitVec::new(),
             vec: Vec::new(),
             marker: PhantomData,
@@ -75,9 +77,18 @@ impl<K: Into<usize> + From<usize>, V> BvMap<K, V> {
             self.next_free = unsafe { slot.next_free };
             self.bitvec.set(next_free, true);
         }
+        self.len += 1;
         K::from(next_free)
     }
 
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     pub fn get(&self, k: K) -> Option<&V> {
         let k = k.into();
         self.bitvec.get(k).and_then(|o| {
@@ -89,6 +100,15 @@ impl<K: Into<usize> + From<usize>, V> BvMap<K, V> {
         })
     }
 
+    pub fn get_mut(&mut self, k: K) -> Option<&mut V> {
+        let k = k.into();
+        if self.bitvec.get(k)? {
+            Some(unsafe { &mut *self.vec[k].value })
+        } else {
+            None
+        }
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = &V> {
         self.vec
             .iter()
@@ -102,12 +122,40 @@ impl<K: Into<usize> + From<usize>, V> BvMap<K, V> {
             })
     }
 
+    pub fn iter_with_keys(&self) -> impl Iterator<Item = (K, &V)> {
+        self.vec
+            .iter()
+            .zip(self.bitvec.iter())
+            .enumerate()
+            .filter_map(|(k, (slot, occupied))| {
+                if occupied {
+                    Some((K::from(k), unsafe { &*slot.value }))
+                } else {
+                    None
+                }
+            })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.vec
+            .iter_mut()
+            .zip(self.bitvec.iter())
+            .filter_map(|(slot, occupied)| {
+                if occupied {
+                    Some(unsafe { &mut *slot.value })
+                } else {
+                    None
+                }
+            })
+    }
+
     pub fn remove(&mut self, k: K) -> Option<V> {
         let k = k.into();
         if self.bitvec.get(k)? {
             self.bitvec.set(k, false);
             let next_free = replace(&mut self.next_free, k);
             let slot = replace(&mut self.vec[k], Slot { next_free });
+            self.len -= 1;
             Some(ManuallyDrop::into_inner(unsafe { slot.value }))
         } else {
             None
@@ -115,6 +163,22 @@ impl<K: Into<usize> + From<usize>, V> BvMap<K, V> {
     }
 }
 
+impl<K, V> BvMap<K, V> {
+    pub fn clear(&mut self) {
+        if needs_drop::<V>() {
+            for (slot, occupied) in self.vec.drain(..).zip(self.bitvec.iter()) {
+                if occupied {
+                    let _ = ManuallyDrop::into_inner(unsafe { slot.value });
+                }
+            }
+        }
+        self.vec.clear();
+        self.bitvec.clear();
+        self.next_free = 0;
+        self.len = 0;
+    }
+}
+
 impl<K, V: Clone> Clone for BvMap<K, V> {
     fn clone(&self) -> Self {
         let mut vec: Vec<Slot<V>> = Vec::with_capacity(self.vec.len());
@@ -133,6 +197,7 @@ impl<K, V: Clone> Clone for BvMap<K, V> {
             vec,
             bitvec: self.bitvec.clone(),
             next_free: self.next_free,
+            len: self.len,
             marker: PhantomData,
         }
     }
@@ -140,13 +205,7 @@ impl<K, V: Clone> Clone for BvMap<K, V> {
 
 impl<K, V> Drop for BvMap<K, V> {
     fn drop(&mut self) {
-        if needs_drop::<V>() {
-            for (slot, occupied) in self.vec.drain(..).zip(self.bitvec.iter()) {
-                if occupied {
-                    let _ = ManuallyDrop::into_inner(unsafe { slot.value });
-                }
-            }
-        }
+        self.clear();
     }
 }
 
@@ -163,4 +222,34 @@ mod tests {
         assert_eq!(bvmap.remove(a2), Some(12));
         assert_eq!(bvmap.get(a2), None);
     }
+
+    #[test]
+    fn iter_with_keys() {
+        let mut bvmap: BvMap<usize, usize> = BvMap::new();
+        let a1 = bvmap.insert(11);
+        let a2 = bvmap.insert(12);
+        let a3 = bvmap.insert(13);
+        bvmap.remove(a2);
+        let pairs: Vec<(usize, usize)> = bvmap.iter_with_keys().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(pairs, vec![(a1, 11), (a3, 13)]);
+    }
+
+    #[test]
+    fn len_get_mut_iter_mut_clear() {
+        let mut bvmap: BvMap<usize, String> = BvMap::new();
+        let a1 = bvmap.insert("a".to_string());
+        let a2 = bvmap.insert("b".to_string());
+        assert_eq!(bvmap.len(), 2);
+        bvmap.get_mut(a1).unwrap().push('c');
+        assert_eq!(bvmap.get(a1).map(String::as_str), Some("ac"));
+        bvmap.remove(a2);
+        for value in bvmap.iter_mut() {
+            value.push('d');
+        }
+        assert_eq!(bvmap.get(a1).map(String::as_str), Some("acd"));
+        assert_eq!(bvmap.len(), 1);
+        bvmap.clear();
+        assert!(bvmap.is_empty());
+        assert_eq!(bvmap.get(a1), None);
+    }
 }
@@ -1,168 +1,661 @@
+use core::error::Error;
+use core::fmt::{self, Debug, Display};
+use core::hash::Hash;
+use core::marker::PhantomData;
 use core::ops::{Index, IndexMut};
 use core::slice::{Iter, IterMut};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
-pub struct Key {
+/// How a key packs its slot index and version, which bounds the number of slots a map can hand
+/// out and how many times each can be reused. Key types declared with [`new_key_type!`] wrap one
+/// of the layouts below.
+///
+/// [`new_key_type!`]: crate::new_key_type
+pub trait Layout: Copy + Eq + Hash + Debug {
+    type Version: Copy + Eq + Hash + Debug;
+
+    /// Largest slot index the layout can hold.
+    const MAX_INDEX: u32;
+
+    /// Largest version the layout can hold. [`Retire`] keeps it to mark retired slots.
+    const LAST: Self::Version;
+
+    /// Version of a slot when it is first handed out.
+    const FIRST: Self::Version;
+
+    fn new(index: u32, version: Self::Version) -> Self;
+
+    fn index(self) -> u32;
+
+    fn version(self) -> Self::Version;
+
+    /// The version after `version`, wrapping to `FIRST` after `LAST`.
+    fn wrapping_next(version: Self::Version) -> Self::Version;
+}
+
+/// A 4 byte key with a 20 bit index and a 12 bit version, for packed references. Holds up to
+/// 1,048,576 slots, each given 4,095 versions under [`Retire`], which keeps the last one to mark
+/// retired slots, or 4,096 wrapping ones under [`Wrap`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Key32(u32);
+
+impl Layout for Key32 {
+    type Version = u16;
+    const MAX_INDEX: u32 = (1 << 20) - 1;
+    const LAST: u16 = (1 << 12) - 1;
+    const FIRST: u16 = 0;
+
+    #[inline]
+    fn new(index: u32, version: u16) -> Key32 {
+        Key32(index | (version as u32) << 20)
+    }
+
+    #[inline]
+    fn index(self) -> u32 {
+        self.0 & Self::MAX_INDEX
+    }
+
+    #[inline]
+    fn version(self) -> u16 {
+        (self.0 >> 20) as u16
+    }
+
+    #[inline]
+    fn wrapping_next(version: u16) -> u16 {
+        (version + 1) & Self::LAST
+    }
+}
+
+/// An 8 byte key with a 32 bit index and a 16 bit version in the low 48 bits, the default.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Key48(u64);
+
+impl Layout for Key48 {
+    type Version = u16;
+    // One short of u32::MAX, which marks free slots and so cannot be a dense index.
+    const MAX_INDEX: u32 = u32::MAX - 1;
+    const LAST: u16 = u16::MAX;
+    const FIRST: u16 = 0;
+
+    #[inline]
+    fn new(index: u32, version: u16) -> Key48 {
+        Key48(index as u64 | (version as u64) << 32)
+    }
+
+    #[inline]
+    fn index(self) -> u32 {
+        self.0 as u32
+    }
+
+    #[inline]
+    fn version(self) -> u16 {
+        (self.0 >> 32) as u16
+    }
+
+    #[inline]
+    fn wrapping_next(version: u16) -> u16 {
+        version.wrapping_add(1)
+    }
+}
+
+/// A 32 bit index with a 64 bit version, 16 bytes with padding, for keys that live as long as the
+/// process. No slot runs out of versions in practice.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Key96 {
     idx: u32,
-    ver: u16,
+    ver: u64,
+}
+
+impl Layout for Key96 {
+    type Version = u64;
+    const MAX_INDEX: u32 = u32::MAX - 1;
+    const LAST: u64 = u64::MAX;
+    const FIRST: u64 = 0;
+
+    #[inline]
+    fn new(idx: u32, ver: u64) -> Key96 {
+        Key96 { idx, ver }
+    }
+
+    #[inline]
+    fn index(self) -> u32 {
+        self.idx
+    }
+
+    #[inline]
+    fn version(self) -> u64 {
+        self.ver
+    }
+
+    #[inline]
+    fn wrapping_next(version: u64) -> u64 {
+        version.wrapping_add(1)
+    }
+}
+
+/// The key to use when a map does not need a key type of its own.
+pub type Key = Key48;
+
+/// Declares key types distinct from [`Key`], so that a key of one [`NaiveSlotMap`] cannot be
+/// passed to another by mistake. Each key wraps [`Key`], or the layout named after a colon:
+///
+/// ```
+/// genbench::new_key_type! {
+///     pub struct MeshKey;
+///     /// A 4 byte key.
+///     pub struct TextureKey: genbench::extra::naive::Key32;
+/// }
+///
+/// let mut meshes = genbench::extra::naive::NaiveSlotMap::<MeshKey, &str>::new();
+/// let cube = meshes.insert("cube");
+/// assert_eq!(meshes[cube], "cube");
+/// ```
+#[macro_export]
+macro_rules! new_key_type {
+    ($(#[$meta:meta])* $vis:vis struct $name:ident; $($rest:tt)*) => {
+        $crate::new_key_type! {
+            $(#[$meta])* $vis struct $name: $crate::extra::naive::Key; $($rest)*
+        }
+    };
+    ($(#[$meta:meta])* $vis:vis struct $name:ident: $layout:ty; $($rest:tt)*) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+        $vis struct $name($layout);
+
+        impl $crate::extra::naive::Layout for $name {
+            type Version = <$layout as $crate::extra::naive::Layout>::Version;
+            const MAX_INDEX: u32 = <$layout as $crate::extra::naive::Layout>::MAX_INDEX;
+            const LAST: Self::Version = <$layout as $crate::extra::naive::Layout>::LAST;
+            const FIRST: Self::Version = <$layout as $crate::extra::naive::Layout>::FIRST;
+
+            #[inline]
+            fn new(index: u32, version: Self::Version) -> Self {
+                $name(<$layout as $crate::extra::naive::Layout>::new(index, version))
+            }
+
+            #[inline]
+            fn index(self) -> u32 {
+                <$layout as $crate::extra::naive::Layout>::index(self.0)
+            }
+
+            #[inline]
+            fn version(self) -> Self::Version {
+                <$layout as $crate::extra::naive::Layout>::version(self.0)
+            }
+
+            #[inline]
+            fn wrapping_next(version: Self::Version) -> Self::Version {
+                <$layout as $crate::extra::naive::Layout>::wrapping_next(version)
+            }
+        }
+
+        $crate::new_key_type! { $($rest)* }
+    };
+    () => {};
+}
+
+/// What a slot's version does when it is bumped past the last value its layout holds, which
+/// happens after that many removals from the one slot. A wider [`Layout`] postpones it.
+pub trait Policy {
+    /// The version after `version`, or `None` once the slot has to be retired.
+    fn next<L: Layout>(version: L::Version) -> Option<L::Version>;
+}
+
+/// Retires a slot for good once its version runs out, so a stale key can never match again. A
+/// retired slot is never reused and keeps its place in the slot table until the map is dropped.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Retire;
+
+impl Policy for Retire {
+    #[inline]
+    fn next<L: Layout>(version: L::Version) -> Option<L::Version> {
+        Some(L::wrapping_next(version)).filter(|&next| next != L::LAST)
+    }
+}
+
+/// Wraps the version back to `FIRST`. A key held across as many removals from its slot as its
+/// layout has versions (65,536 with [`Key48`], 4,096 with [`Key32`]) becomes valid again while
+/// the slot is occupied and reads whatever was inserted there since (the ABA problem), so this is
+/// only sound when keys never live that long. A free slot never matches a key.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Wrap;
+
+impl Policy for Wrap {
+    #[inline]
+    fn next<L: Layout>(version: L::Version) -> Option<L::Version> {
+        Some(L::wrapping_next(version))
+    }
+}
+
+/// Returned with the value by [`NaiveSlotMap::try_insert`] when every slot index the layout can
+/// hold is in use or retired.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CapacityError<T>(pub T);
+
+impl<T> CapacityError<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
 }
 
+impl<T> Debug for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CapacityError(..)")
+    }
+}
+
+impl<T> Display for CapacityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("no slot index left in the key layout")
+    }
+}
+
+impl<T> Error for CapacityError<T> {}
+
+/// `outer` of a slot that holds no value. Versions can repeat under [`Wrap`], so a matching
+/// version alone does not mean a slot is occupied.
+const VACANT: u32 = u32::MAX;
+
 #[derive(Clone)]
-struct Slot {
+struct Slot<V> {
     outer: u32,
-    version: u16,
+    version: V,
 }
 
+impl<V: Eq> Slot<V> {
+    /// True if the slot holds the value of a key with this version.
+    #[inline]
+    fn matches(&self, version: V) -> bool {
+        self.version == version && self.outer != VACANT
+    }
+}
+
+/// `inner` holds every slot index: those of live values in dense order, then free slots, then
+/// the last `retired` slots, which are never handed out again.
 #[derive(Clone)]
-pub struct NaiveSlotMap<T> {
-    slots: Vec<Slot>,
+pub struct NaiveSlotMap<K: Layout, T, P: Policy = Retire> {
+    slots: Vec<Slot<K::Version>>,
     data: Vec<T>,
     inner: Vec<u32>,
+    retired: usize,
+    policy: PhantomData<P>,
 }
 
-impl<T> NaiveSlotMap<T> {
-    pub fn new() -> NaiveSlotMap<T> {
+impl<K: Layout, T, P: Policy> NaiveSlotMap<K, T, P> {
+    pub fn new() -> NaiveSlotMap<K, T, P> {
         NaiveSlotMap {
             slots: Vec::new(),
             data: Vec::new(),
             inner: Vec::new(),
+            retired: 0,
+            policy: PhantomData,
         }
     }
 
-    pub fn with_capacity(capacity: usize) -> NaiveSlotMap<T> {
+    pub fn with_capacity(capacity: usize) -> NaiveSlotMap<K, T, P> {
         NaiveSlotMap {
             slots: Vec::with_capacity(capacity),
             data: Vec::with_capacity(capacity),
             inner: Vec::with_capacity(capacity),
+            retired: 0,
+            policy: PhantomData,
         }
     }
 
+    /// Number of slots retired because their version ran out.
+    #[inline]
+    pub fn retired(&self) -> usize {
+        self.retired
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.data.len()
     }
 
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     #[inline]
     pub fn clear(&mut self) {
         self.data.clear();
-        for slot in &mut self.slots {
-            slot.version += 1;
+        let mut active = self.slots.len() - self.retired;
+        let mut position = 0;
+        while position < active {
+            let slot = unsafe {
+                let index = *self.inner.get_unchecked(position);
+                self.slots.get_unchecked_mut(index as usize)
+            };
+            slot.outer = VACANT;
+            match P::next::<K>(slot.version) {
+                Some(version) => {
+                    slot.version = version;
+                    position += 1;
+                }
+                None => {
+                    slot.version = K::LAST;
+                    active -= 1;
+                    self.inner.swap(position, active);
+                }
+            }
         }
-        let mut counter = 0;
-        for slot in &mut self.inner {
-            *slot = counter;
-            counter += 1;
+        self.retired = self.slots.len() - active;
+        if self.retired == 0 {
+            for (counter, slot) in self.inner.iter_mut().enumerate() {
+                *slot = counter as u32;
+            }
+        } else {
+            self.inner[..active].sort_unstable();
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If every slot index the layout can hold is in use or retired, see [`Self::try_insert`].
+    #[inline]
+    pub fn insert(&mut self, value: T) -> K {
+        match self.try_insert(value) {
+            Ok(key) => key,
+            Err(e) => panic!("{}", e),
         }
     }
 
     #[inline]
-    pub fn insert(&mut self, value: T) -> Key {
-        let index = self.data.len() as u32;
-        self.data.push(value);
-        if index as usize == self.slots.len() {
+    pub fn try_insert(&mut self, value: T) -> Result<K, CapacityError<T>> {
+        let len = self.data.len();
+        if len > K::MAX_INDEX as usize {
+            return Err(CapacityError(value));
+        }
+        let index = match u32::try_from(len) {
+            Ok(index) => index,
+            Err(_) => return Err(CapacityError(value)),
+        };
+        if len == self.slots.len() - self.retired {
+            if self.slots.len() > K::MAX_INDEX as usize {
+                return Err(CapacityError(value));
+            }
+            self.data.push(value);
+            let key_index = self.slots.len() as u32;
             self.slots.push(Slot {
                 outer: index,
-                version: 0,
+                version: K::FIRST,
             });
-            self.inner.push(index);
-            Key { idx: index, ver: 0 }
+            self.inner.push(key_index);
+            // Keep the retired slots behind the free ones.
+            if self.retired > 0 {
+                self.inner.swap(index as usize, key_index as usize);
+            }
+            Ok(K::new(key_index, K::FIRST))
         } else {
+            self.data.push(value);
             unsafe {
                 let key_index = *self.inner.get_unchecked(index as usize);
                 let slot = self.slots.get_unchecked_mut(key_index as usize);
-                let version = slot.version;
                 slot.outer = index;
-                Key {
-                    idx: key_index,
-                    ver: version,
-                }
+                Ok(K::new(key_index, slot.version))
             }
         }
     }
 
     #[inline]
-    pub fn remove(&mut self, key: Key) -> Option<T> {
-        let slot = self.slots.get_mut(key.idx as usize)?;
-        if slot.version != key.ver {
+    pub fn remove(&mut self, key: K) -> Option<T> {
+        let slot = self.slots.get_mut(key.index() as usize)?;
+        if !slot.matches(key.version()) {
             return None;
         }
         let remove_index = slot.outer;
-        slot.version += 1;
+        let next = P::next::<K>(slot.version);
+        slot.version = next.unwrap_or(K::LAST);
         let removed = self.data.swap_remove(remove_index as usize);
         unsafe {
             let slot = self.inner.get_unchecked_mut(self.data.len());
             let update_index = *slot;
-            *slot = key.idx;
+            *slot = key.index();
             *self.inner.get_unchecked_mut(remove_index as usize) = update_index;
             self.slots.get_unchecked_mut(update_index as usize).outer = remove_index;
-            Some(removed)
+            self.slots.get_unchecked_mut(key.index() as usize).outer = VACANT;
+        }
+        if next.is_none() {
+            self.retire(self.data.len());
         }
+        Some(removed)
     }
 
+    /// Keeps only the values `f` returns true for, in one pass over the dense array. Kept values
+    /// stay in their relative order.
     #[inline]
-    pub fn get(&self, key: Key) -> Option<&T> {
-        let slot = self.slots.get(key.idx as usize)?;
-        if slot.version != key.ver {
+    pub fn retain(&mut self, mut f: impl FnMut(K, &mut T) -> bool) {
+        self.drain_filter(|key, value| !f(key, value))
+            .for_each(drop);
+    }
+
+    /// Removes every value, yielding each with its key in reverse dense order. Values the iterator
+    /// did not get to are removed when it is dropped.
+    #[inline]
+    pub fn drain(&mut self) -> Drain<'_, K, T, P> {
+        Drain { map: self, end: 0 }
+    }
+
+    /// Removes the values `f` returns true for, yielding each with its key. `f` sees every value in
+    /// dense order before the first is yielded, moving the ones it keeps to the front in their
+    /// relative order, and the others are then taken from the back like [`Self::drain`].
+    #[inline]
+    pub fn drain_filter(&mut self, mut f: impl FnMut(K, &mut T) -> bool) -> Drain<'_, K, T, P> {
+        let mut kept = 0;
+        for position in 0..self.data.len() {
+            let (key, value) = unsafe {
+                let idx = *self.inner.get_unchecked(position);
+                let version = self.slots.get_unchecked(idx as usize).version;
+                (K::new(idx, version), self.data.get_unchecked_mut(position))
+            };
+            if !f(key, value) {
+                if kept != position {
+                    self.swap_dense(kept, position);
+                }
+                kept += 1;
+            }
+        }
+        Drain {
+            map: self,
+            end: kept,
+        }
+    }
+
+    /// Swaps two values of the dense array along with the slots that point at them, so the map
+    /// stays consistent even if a `drain_filter` predicate panics.
+    #[inline]
+    fn swap_dense(&mut self, a: usize, b: usize) {
+        self.data.swap(a, b);
+        self.inner.swap(a, b);
+        unsafe {
+            let idx = *self.inner.get_unchecked(a);
+            self.slots.get_unchecked_mut(idx as usize).outer = a as u32;
+            let idx = *self.inner.get_unchecked(b);
+            self.slots.get_unchecked_mut(idx as usize).outer = b as u32;
+        }
+    }
+
+    /// Removes the last value of the dense array, which leaves its slot first among the free
+    /// ones without moving anything.
+    #[inline]
+    fn pop(&mut self) -> Option<(K, T)> {
+        let value = self.data.pop()?;
+        let position = self.data.len();
+        let idx = unsafe { *self.inner.get_unchecked(position) };
+        let slot = unsafe { self.slots.get_unchecked_mut(idx as usize) };
+        let key = K::new(idx, slot.version);
+        let next = P::next::<K>(slot.version);
+        slot.version = next.unwrap_or(K::LAST);
+        slot.outer = VACANT;
+        if next.is_none() {
+            self.retire(position);
+        }
+        Some((key, value))
+    }
+
+    /// Moves the just freed slot at `position` of `inner` behind the free ones, for good.
+    #[inline]
+    fn retire(&mut self, position: usize) {
+        self.retired += 1;
+        let last_free = self.slots.len() - self.retired;
+        self.inner.swap(position, last_free);
+    }
+
+    #[inline]
+    pub fn get(&self, key: K) -> Option<&T> {
+        let slot = self.slots.get(key.index() as usize)?;
+        if !slot.matches(key.version()) {
             return None;
         }
         unsafe { Some(self.data.get_unchecked(slot.outer as usize)) }
     }
 
     #[inline]
-    pub fn get_mut(&mut self, key: Key) -> Option<&mut T> {
-        let slot = self.slots.get(key.idx as usize)?;
-        if slot.version != key.ver {
+    pub fn get_mut(&mut self, key: K) -> Option<&mut T> {
+        let slot = self.slots.get(key.index() as usize)?;
+        if !slot.matches(key.version()) {
             return None;
         }
         unsafe { Some(self.data.get_unchecked_mut(slot.outer as usize)) }
     }
 
+    /// # Safety
+    ///
+    /// `key` must have been returned by this map, so its index is within the slot table.
     #[inline]
-    pub unsafe fn get_unchecked(&self, key: Key) -> Option<&T> {
-        let slot = self.slots.get_unchecked(key.idx as usize);
-        if slot.version != key.ver {
+    pub unsafe fn get_unchecked(&self, key: K) -> Option<&T> {
+        let slot = self.slots.get_unchecked(key.index() as usize);
+        if !slot.matches(key.version()) {
             return None;
         }
         Some(self.data.get_unchecked(slot.outer as usize))
     }
 
+    /// # Safety
+    ///
+    /// `key` must have been returned by this map, so its index is within the slot table.
     #[inline]
-    pub unsafe fn get_unchecked_mut(&mut self, key: Key) -> Option<&mut T> {
-        let slot = self.slots.get_unchecked(key.idx as usize);
-        if slot.version != key.ver {
+    pub unsafe fn get_unchecked_mut(&mut self, key: K) -> Option<&mut T> {
+        let slot = self.slots.get_unchecked(key.index() as usize);
+        if !slot.matches(key.version()) {
             return None;
         }
         Some(self.data.get_unchecked_mut(slot.outer as usize))
     }
 
     #[inline]
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         self.data.iter()
     }
 
     #[inline]
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         self.data.iter_mut()
     }
+
+    /// Iterates over the values in dense order along with their keys. `inner` maps each dense
+    /// position back to its slot, which holds the current version.
+    #[inline]
+    pub fn iter_with_keys(&self) -> impl Iterator<Item = (K, &T)> {
+        self.keys().zip(&self.data)
+    }
+
+    /// Like [`Self::iter_with_keys`], with mutable values.
+    #[inline]
+    pub fn iter_mut_with_keys(&mut self) -> impl Iterator<Item = (K, &mut T)> {
+        let slots = &self.slots;
+        self.inner
+            .iter()
+            .zip(&mut self.data)
+            .map(move |(&idx, value)| {
+                let ver = unsafe { slots.get_unchecked(idx as usize).version };
+                (K::new(idx, ver), value)
+            })
+    }
+
+    /// Keys of the values in dense order, the order of [`Self::iter`].
+    #[inline]
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        self.inner[..self.data.len()].iter().map(move |&idx| {
+            let ver = unsafe { self.slots.get_unchecked(idx as usize).version };
+            K::new(idx, ver)
+        })
+    }
+
+    /// Key of the value at `dense_index` in [`Self::iter`] order, or `None` past the end. Keys stay
+    /// valid while values move, so the key is what to hold on to when removing while iterating.
+    #[inline]
+    pub fn key_of_index(&self, dense_index: usize) -> Option<K> {
+        if dense_index >= self.data.len() {
+            return None;
+        }
+        unsafe {
+            let idx = *self.inner.get_unchecked(dense_index);
+            Some(K::new(idx, self.slots.get_unchecked(idx as usize).version))
+        }
+    }
 }
 
-impl<T> Index<Key> for NaiveSlotMap<T> {
+/// Removes values from the back of a map's dense array, from [`NaiveSlotMap::drain`] and
+/// [`NaiveSlotMap::drain_filter`]. The map is consistent after every step, so leaking the iterator
+/// only leaves values in the map.
+pub struct Drain<'a, K: Layout, T, P: Policy = Retire> {
+    map: &'a mut NaiveSlotMap<K, T, P>,
+    /// Length the map is drained down to.
+    end: usize,
+}
+
+impl<K: Layout, T, P: Policy> Iterator for Drain<'_, K, T, P> {
+    type Item = (K, T);
+
+    #[inline]
+    fn next(&mut self) -> Option<(K, T)> {
+        if self.map.len() > self.end {
+            self.map.pop()
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let left = self.map.len() - self.end;
+        (left, Some(left))
+    }
+}
+
+impl<K: Layout, T, P: Policy> ExactSizeIterator for Drain<'_, K, T, P> {}
+
+impl<K: Layout, T, P: Policy> Drop for Drain<'_, K, T, P> {
+    fn drop(&mut self) {
+        self.for_each(drop);
+    }
+}
+
+impl<K: Layout, T, P: Policy> Default for NaiveSlotMap<K, T, P> {
+    fn default() -> NaiveSlotMap<K, T, P> {
+        NaiveSlotMap::new()
+    }
+}
+
+impl<K: Layout, T, P: Policy> Index<K> for NaiveSlotMap<K, T, P> {
     type Output = T;
 
     #[inline]
-    fn index(&self, key: Key) -> &Self::Output {
+    fn index(&self, key: K) -> &Self::Output {
         self.get(key).unwrap()
     }
 }
 
-impl<T> IndexMut<Key> for NaiveSlotMap<T> {
+impl<K: Layout, T, P: Policy> IndexMut<K> for NaiveSlotMap<K, T, P> {
     #[inline]
-    fn index_mut(&mut self, key: Key) -> &mut Self::Output {
+    fn index_mut(&mut self, key: K) -> &mut Self::Output {
         self.get_mut(key).unwrap()
     }
 }
 
-impl<'a, T> IntoIterator for &'a NaiveSlotMap<T> {
+impl<'a, K: Layout, T, P: Policy> IntoIterator for &'a NaiveSlotMap<K, T, P> {
     type Item = &'a T;
     type IntoIter = Iter<'a, T>;
 
@@ -172,7 +665,7 @@ impl<'a, T> IntoIterator for &'a NaiveSlotMap<T> {
     }
 }
 
-impl<'a, T> IntoIterator for &'a mut NaiveSlotMap<T> {
+impl<'a, K: Layout, T, P: Policy> IntoIterator for &'a mut NaiveSlotMap<K, T, P> {
     type Item = &'a mut T;
     type IntoIter = IterMut<'a, T>;
 
@@ -193,7 +686,7 @@ mod tests {
 
     #[test]
     fn add_get() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let first = map.insert(0);
 
         assert_eq!(map.get(first), Some(&0));
@@ -202,7 +695,7 @@ mod tests {
 
     #[test]
     fn add_clear() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let _first = map.insert(0);
         map.clear();
 
@@ -211,7 +704,7 @@ mod tests {
 
     #[test]
     fn add_twice_get_second() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let _first = map.insert(0);
         let second = map.insert(1);
 
@@ -221,7 +714,7 @@ mod tests {
 
     #[test]
     fn add_twice_get_first() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let first = map.insert(0);
         let _second = map.insert(1);
 
@@ -231,7 +724,7 @@ mod tests {
 
     #[test]
     fn add_remove() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let first = map.insert(0);
         let index = map.remove(first);
 
@@ -241,7 +734,7 @@ mod tests {
 
     #[test]
     fn add_remove_old_key() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let first = map.insert(0);
         map.remove(first);
 
@@ -251,7 +744,7 @@ mod tests {
 
     #[test]
     fn add_get_old_key() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let first = map.insert(0);
         map.remove(first);
 
@@ -261,7 +754,7 @@ mod tests {
 
     #[test]
     fn add_twice_remove_second() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let _first = map.insert(0);
         let second = map.insert(1);
         let index = map.remove(second);
@@ -272,7 +765,7 @@ mod tests {
 
     #[test]
     fn add_twice_remove_first() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let first = map.insert(0);
         let _second = map.insert(1);
         let index = map.remove(first);
@@ -283,7 +776,7 @@ mod tests {
 
     #[test]
     fn add_twice_remove_first_swaps() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let first = map.insert(0);
         let second = map.insert(1);
         map.remove(first);
@@ -294,7 +787,7 @@ mod tests {
 
     #[test]
     fn add_thrice_remove_first_swaps_ignores_second() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let first = map.insert(0);
         let second = map.insert(1);
         let _third = map.insert(2);
@@ -304,9 +797,130 @@ mod tests {
         assert_eq!(map.len(), 2);
     }
 
+    #[test]
+    fn iter_with_keys_after_swap() {
+        let mut map = NaiveSlotMap::<Key, usize>::new();
+        let first = map.insert(0);
+        let second = map.insert(1);
+        let third = map.insert(2);
+        map.remove(first);
+        let fourth = map.insert(3);
+
+        let pairs: Vec<(Key, usize)> = map.iter_with_keys().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(pairs, vec![(third, 2), (second, 1), (fourth, 3)]);
+    }
+
+    #[test]
+    fn keys_and_key_of_index() {
+        let mut map = NaiveSlotMap::<Key, usize>::new();
+        let first = map.insert(0);
+        let second = map.insert(1);
+        let third = map.insert(2);
+        map.remove(first);
+
+        assert_eq!(map.keys().collect::<Vec<Key>>(), vec![third, second]);
+        assert_eq!(map.key_of_index(0), Some(third));
+        assert_eq!(map.key_of_index(1), Some(second));
+        assert_eq!(map.key_of_index(2), None);
+    }
+
+    #[test]
+    fn remove_while_iterating() {
+        let mut map = NaiveSlotMap::<Key, usize>::new();
+        for value in 0..10 {
+            map.insert(value);
+        }
+        for (_, value) in map.iter_mut_with_keys() {
+            *value *= 10;
+        }
+        let mut index = 0;
+        while let Some(key) = map.key_of_index(index) {
+            if map[key].is_multiple_of(20) {
+                map.remove(key);
+            } else {
+                index += 1;
+            }
+        }
+
+        let mut values: Vec<usize> = map.iter().copied().collect();
+        values.sort_unstable();
+        assert_eq!(values, vec![10, 30, 50, 70, 90]);
+        assert!(map.iter_with_keys().all(|(key, value)| map[key] == *value));
+    }
+
+    #[test]
+    fn retain_keeps_order() {
+        let mut map = NaiveSlotMap::<Key, usize>::new();
+        let keys: Vec<Key> = (0..10).map(|value| map.insert(value)).collect();
+        map.retain(|_, value| {
+            *value += 100;
+            *value % 3 == 0
+        });
+
+        assert_eq!(
+            map.iter().copied().collect::<Vec<usize>>(),
+            vec![102, 105, 108]
+        );
+        for (value, &key) in keys.iter().enumerate() {
+            assert_eq!(map.get(key).is_some(), (value + 100) % 3 == 0);
+        }
+        assert!(map.iter_with_keys().all(|(key, value)| map[key] == *value));
+
+        let reused: Vec<Key> = (0..7).map(|value| map.insert(value)).collect();
+        assert!(reused.iter().all(|key| !keys.contains(key)));
+        assert_eq!(map.len(), 10);
+    }
+
+    #[test]
+    fn drain_everything() {
+        let mut map = NaiveSlotMap::<Key, usize>::new();
+        let keys: Vec<Key> = (0..5).map(|value| map.insert(value)).collect();
+        map.remove(keys[1]);
+
+        let drained: Vec<(Key, usize)> = map.drain().collect();
+        assert_eq!(
+            drained,
+            vec![(keys[3], 3), (keys[2], 2), (keys[4], 4), (keys[0], 0)]
+        );
+        assert!(map.is_empty());
+        assert!(keys.iter().all(|&key| map.get(key).is_none()));
+
+        let key = map.insert(5);
+        assert_eq!(map[key], 5);
+    }
+
+    #[test]
+    fn drain_filter_dropped_early() {
+        let mut map = NaiveSlotMap::<Key, String>::new();
+        let keys: Vec<Key> = (0..6).map(|value| map.insert(value.to_string())).collect();
+        let mut drain = map.drain_filter(|_, value| value.parse::<usize>().unwrap() >= 3);
+        assert_eq!(drain.len(), 3);
+        assert_eq!(drain.next(), Some((keys[5], "5".to_string())));
+        drop(drain);
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.keys().collect::<Vec<Key>>(), keys[..3]);
+        assert!(keys[3..].iter().all(|&key| map.get(key).is_none()));
+    }
+
+    #[test]
+    fn retain_retires_exhausted_slots() {
+        let mut map = NaiveSlotMap::<Key32, usize>::new();
+        let oldest = map.insert(0);
+        for generation in 1..Key32::LAST as usize {
+            map.retain(|_, _| false);
+            assert_eq!(map.insert(generation).index(), oldest.index());
+        }
+        map.insert(0);
+        map.retain(|key, _| key.index() != oldest.index());
+        assert_eq!(map.retired(), 1);
+        assert_eq!(map.len(), 1);
+        assert_ne!(map.insert(1).index(), oldest.index());
+    }
+
     #[test]
     fn complex() {
-        let mut map = NaiveSlotMap::<usize>::new();
+        let mut map = NaiveSlotMap::<Key, usize>::new();
         let first = map.insert(0);
         let second = map.insert(1);
 
@@ -361,4 +975,221 @@ mod tests {
         assert_eq!(map.get(forth), Some(&3));
         assert_eq!(map.len(), 4);
     }
+
+    #[test]
+    fn layouts_round_trip() {
+        let key = Key32::new(Key32::MAX_INDEX, Key32::LAST);
+        assert_eq!(
+            (key.index(), key.version()),
+            (Key32::MAX_INDEX, Key32::LAST)
+        );
+        assert_eq!(Key32::wrapping_next(Key32::LAST), 0);
+        assert_eq!(core::mem::size_of::<Key32>(), 4);
+
+        let key = Key48::new(u32::MAX, u16::MAX);
+        assert_eq!((key.index(), key.version()), (u32::MAX, u16::MAX));
+        assert_eq!(core::mem::size_of::<Key48>(), 8);
+
+        let key = Key96::new(7, u64::MAX - 1);
+        assert_eq!((key.index(), key.version()), (7, u64::MAX - 1));
+    }
+
+    #[test]
+    fn retire_exhausted_slot() {
+        let mut map = NaiveSlotMap::<Key, usize>::new();
+        let first = map.insert(0);
+        let mut key = map.insert(1);
+        let oldest = key;
+        for generation in 1..u16::MAX as usize {
+            assert_eq!(map.remove(key), Some(generation));
+            assert_eq!(map.retired(), 0);
+            key = map.insert(generation + 1);
+            assert_eq!(key.index(), oldest.index());
+        }
+        assert_eq!(key.version(), u16::MAX - 1);
+        map.remove(key);
+        assert_eq!(map.retired(), 1);
+        assert_eq!(map.get(oldest), None);
+        assert_eq!(map.get(key), None);
+
+        let fresh = map.insert(2);
+        assert_ne!(fresh.index(), oldest.index());
+        assert_eq!(map.get(fresh), Some(&2));
+        assert_eq!(map.get(first), Some(&0));
+        map.remove(first);
+        let reused = map.insert(3);
+        assert_eq!(reused.index(), first.index());
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn retire_exhausted_packed_slot() {
+        let mut map = NaiveSlotMap::<Key32, usize>::new();
+        let oldest = map.insert(0);
+        let mut key = oldest;
+        for generation in 1..Key32::LAST as usize {
+            map.remove(key);
+            key = map.insert(generation);
+            assert_eq!(key.index(), oldest.index());
+        }
+        map.remove(key);
+        assert_eq!(map.retired(), 1);
+        assert_ne!(map.insert(0).index(), oldest.index());
+    }
+
+    #[test]
+    fn clear_retires_exhausted_slots() {
+        let mut map = NaiveSlotMap::<Key, usize>::new();
+        let first = map.insert(0);
+        for _ in 1..u16::MAX {
+            map.clear();
+        }
+        assert_eq!(map.retired(), 0);
+        map.clear();
+        assert_eq!(map.retired(), 1);
+        assert_eq!(map.get(first), None);
+
+        let keys: Vec<Key> = (0..3).map(|value| map.insert(value)).collect();
+        assert!(keys.iter().all(|key| key.index() != first.index()));
+        map.clear();
+        let pairs: Vec<(Key, usize)> = (0..3).map(|value| (map.insert(value), value)).collect();
+        let listed: Vec<(Key, usize)> = map.iter_with_keys().map(|(k, v)| (k, *v)).collect();
+        assert_eq!(listed, pairs);
+        assert!(keys.iter().all(|&key| map.get(key).is_none()));
+    }
+
+    #[test]
+    fn wrap_reuses_versions() {
+        let mut map = NaiveSlotMap::<Key, usize, Wrap>::new();
+        let oldest = map.insert(0);
+        let mut key = oldest;
+        for generation in 1..=u16::MAX as usize + 1 {
+            map.remove(key);
+            key = map.insert(generation);
+        }
+        assert_eq!(map.retired(), 0);
+        assert_eq!(key, oldest);
+        assert_eq!(map.get(oldest), Some(&(u16::MAX as usize + 1)));
+    }
+
+    #[test]
+    fn wrapped_version_of_free_slot() {
+        let mut map = NaiveSlotMap::<Key32, u32, Wrap>::new();
+        let first = map.insert(0);
+        let mut key = first;
+        for generation in 1..=Key32::LAST as u32 {
+            map.remove(key);
+            key = map.insert(generation);
+        }
+        map.remove(key);
+
+        assert!(map.is_empty());
+        assert_eq!(map.get(first), None);
+        assert_eq!(map.get_mut(first), None);
+        assert_eq!(map.remove(first), None);
+        let reused = map.insert(7);
+        assert_eq!(reused, first);
+        assert_eq!(map[first], 7);
+    }
+
+    #[test]
+    fn wide_versions_outlast_u16() {
+        let mut map = NaiveSlotMap::<Key96, usize>::new();
+        let oldest = map.insert(0);
+        let mut key = oldest;
+        for generation in 1..=u16::MAX as usize + 1 {
+            map.remove(key);
+            key = map.insert(generation);
+        }
+        assert_eq!(map.retired(), 0);
+        assert_eq!(key.version(), u16::MAX as u64 + 1);
+        assert_eq!(map.get(oldest), None);
+        assert_eq!(map[key], u16::MAX as usize + 1);
+    }
+
+    /// A layout with room for four slots, to run out of them quickly.
+    #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+    struct Tiny(Key32);
+
+    impl Layout for Tiny {
+        type Version = u16;
+        const MAX_INDEX: u32 = 3;
+        const LAST: u16 = Key32::LAST;
+        const FIRST: u16 = Key32::FIRST;
+
+        fn new(index: u32, version: u16) -> Tiny {
+            Tiny(Key32::new(index, version))
+        }
+
+        fn index(self) -> u32 {
+            self.0.index()
+        }
+
+        fn version(self) -> u16 {
+            self.0.version()
+        }
+
+        fn wrapping_next(version: u16) -> u16 {
+            Key32::wrapping_next(version)
+        }
+    }
+
+    #[test]
+    fn retired_slots_count_against_capacity() {
+        let mut map = NaiveSlotMap::<Tiny, usize>::new();
+        let keys: Vec<Tiny> = (0..4).map(|value| map.insert(value)).collect();
+        assert_eq!(map.try_insert(4), Err(CapacityError(4)));
+
+        let mut key = keys[3];
+        loop {
+            map.remove(key);
+            if map.retired() > 0 {
+                break;
+            }
+            key = map.insert(3);
+        }
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.try_insert(5).map_err(CapacityError::into_inner), Err(5));
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn packed_capacity_error() {
+        let mut map = NaiveSlotMap::<Key32, ()>::new();
+        for _ in 0..=Key32::MAX_INDEX {
+            map.insert(());
+        }
+        assert_eq!(map.try_insert(()), Err(CapacityError(())));
+        assert_eq!(map.len(), Key32::MAX_INDEX as usize + 1);
+
+        let last = map.iter_with_keys().last().unwrap().0;
+        assert_eq!(last.index(), Key32::MAX_INDEX);
+        map.remove(last);
+        assert_eq!(
+            map.try_insert(()).map(|key| key.index()),
+            Ok(Key32::MAX_INDEX)
+        );
+    }
+
+    crate::new_key_type! {
+        struct MeshKey;
+        struct TextureKey: Key32;
+    }
+
+    #[test]
+    fn typed_keys() {
+        let mut meshes = NaiveSlotMap::<MeshKey, &str>::new();
+        let mut textures = NaiveSlotMap::<TextureKey, &str>::new();
+        let cube = meshes.insert("cube");
+        let brick = textures.insert("brick");
+
+        assert_eq!(meshes[cube], "cube");
+        assert_eq!(textures[brick], "brick");
+        assert_eq!(core::mem::size_of::<TextureKey>(), 4);
+
+        meshes.remove(cube);
+        assert_eq!(meshes.get(cube), None);
+        let keys: Vec<MeshKey> = meshes.iter_with_keys().map(|(key, _)| key).collect();
+        assert!(keys.is_empty());
+    }
 }
@@ -1,121 +1,69 @@
-use crate::Crate;
-use criterion::{black_box, BatchSize, Bencher};
-use generational_indextree::Arena;
+use crate::{Adapter, Arena, Capabilities, Tag};
+use generational_indextree::{Node, NodeId};
 
-pub struct CrateGenerationalIndextree();
+pub type CrateGenerationalIndextree<T> = Adapter<generational_indextree::Arena<T>>;
 
-impl Crate for CrateGenerationalIndextree {
-    fn name(&self) -> &'static str {
-        "generational-indextree"
-    }
-
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let arena = Arena::new();
+impl<T: Clone + 'static> Arena<T> for generational_indextree::Arena<T> {
+    type Key = NodeId;
+    const NAME: &'static str = "generational-indextree";
+    const LINK: &'static str = "https://gitlab.com/barry.van.acker/generational-indextree";
+    const TAGS: &'static [Tag] = &[Tag::Tree, Tag::UnsafeFree];
+    const CAPABILITIES: Capabilities = Capabilities {
+        generational: true,
+        generational_clear: false,
+        iter_mut: false,
+        with_capacity: true,
+        retain: false,
+    };
 
-        b.iter_batched(
-            || arena.clone(),
-            |mut arena| {
-                for i in 0..size {
-                    arena.new_node(i);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn new() -> Self {
+        generational_indextree::Arena::new()
     }
 
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut arena = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(arena.new_node(a));
-        }
-        for a in 0..size {
-            keys[a].remove(&mut arena);
-        }
+    fn with_capacity(capacity: usize) -> Option<Self> {
+        Some(generational_indextree::Arena::with_capacity(capacity))
+    }
 
-        b.iter_batched(
-            || arena.clone(),
-            |mut arena| {
-                for i in 0..size {
-                    arena.new_node(i);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn insert(&mut self, value: T) -> NodeId {
+        self.new_node(value)
     }
 
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut arena = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(arena.new_node(a));
+    fn remove(&mut self, key: NodeId) -> bool {
+        // NodeId::remove panics on a stale id.
+        if self.get(key).is_none() {
+            return false;
         }
+        key.remove(self);
+        true
+    }
 
-        b.iter_batched(
-            || arena.clone(),
-            |mut arena| {
-                for i in lookup {
-                    keys[*i].remove(&mut arena);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn get(&self, key: NodeId) -> Option<&T> {
+        self.get(key).map(Node::get)
     }
 
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut arena = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(arena.new_node(a));
-        }
+    fn get_mut(&mut self, key: NodeId) -> Option<&mut T> {
+        self.get_mut(key).map(Node::get_mut)
+    }
 
-        b.iter_batched(
-            || arena.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(keys[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.iter().map(Node::get)
     }
 
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut arena = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(arena.new_node(a));
-        }
+    fn iter_with_keys(&self) -> impl Iterator<Item = (NodeId, &T)> {
+        self.iter_pairs().map(|(id, node)| (id, node.get()))
+    }
 
-        b.iter_batched_ref(
-            || arena.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+        // generational-indextree has no mutable iteration.
+        None::<std::iter::Empty<&mut T>>
     }
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut arena = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(arena.new_node(a));
-        }
-        for i in lookup {
-            keys[*i].remove(&mut arena);
-        }
+    fn len(&self) -> usize {
+        self.count()
+    }
 
-        b.iter_batched_ref(
-            || arena.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn clear(&mut self) {
+        // generational-indextree has no clear, so start over.
+        *self = generational_indextree::Arena::new();
     }
 }
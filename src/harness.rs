@@ -0,0 +1,472 @@
+use crate::memory::{self, Tracker, Usage};
+use crate::select::Selection;
+use crate::trace::Trace;
+use crate::{Capabilities, Crate, Payload, Tag};
+use criterion::{BatchSize, Bencher};
+use std::fmt::Write;
+use std::mem::size_of;
+use std::time::Duration;
+
+/// Describes a benchmark group and exactly what its measurement covers.
+pub struct Workload {
+    /// Criterion group name.
+    pub group: &'static str,
+    /// Kinds of operation timed, such as `get` or `iter`, used to pick workloads to benchmark.
+    pub tags: &'static [&'static str],
+    /// State prepared once, outside of timing.
+    pub setup: &'static str,
+    /// The only work inside the timed region.
+    pub timed: &'static str,
+    /// Only implementations claiming all of these are run.
+    pub requires: Capabilities,
+}
+
+pub const INSERT: Workload = Workload {
+    group: "Insert",
+    tags: &["inserts"],
+    setup: "Create an empty arena.",
+    timed: "Insert size elements.",
+    requires: Capabilities::NONE,
+};
+
+pub const INSERT_RESERVED: Workload = Workload {
+    group: "InsertReserved",
+    tags: &["inserts"],
+    setup: "Create an empty arena with capacity for size elements.",
+    timed: "Insert size elements.",
+    requires: Capabilities {
+        with_capacity: true,
+        ..Capabilities::NONE
+    },
+};
+
+pub const INSERT_USED: Workload = Workload {
+    group: "InsertUsed",
+    tags: &["inserts"],
+    setup: "Insert size elements, remove all of them sequentially.",
+    timed: "Insert size elements.",
+    requires: Capabilities::NONE,
+};
+
+pub const REMOVE: Workload = Workload {
+    group: "Remove",
+    tags: &["removes"],
+    setup: "Insert size elements.",
+    timed: "Remove every element in a shuffled order.",
+    requires: Capabilities::NONE,
+};
+
+pub const REMOVE_HALF: Workload = Workload {
+    group: "RemoveHalf",
+    tags: &["removes"],
+    setup: "Insert size elements.",
+    timed: "Collect the keys of a random half of the elements while iterating, then remove them \
+            one at a time.",
+    requires: Capabilities::NONE,
+};
+
+pub const RETAIN_HALF: Workload = Workload {
+    group: "RetainHalf",
+    tags: &["removes"],
+    setup: "Insert size elements.",
+    timed: "Remove a random half of the elements with the implementation's own retain, dropping \
+            them by the same pattern in visit order.",
+    requires: Capabilities {
+        retain: true,
+        ..Capabilities::NONE
+    },
+};
+
+pub const GET: Workload = Workload {
+    group: "Get",
+    tags: &["lookups"],
+    setup: "Insert size elements.",
+    timed: "Get size randomly chosen elements.",
+    requires: Capabilities::NONE,
+};
+
+pub const ITER: Workload = Workload {
+    group: "Iter",
+    tags: &["iteration"],
+    setup: "Insert size elements.",
+    timed: "Iterate over every element's value.",
+    requires: Capabilities::NONE,
+};
+
+pub const ITER_KEYS_VALUES: Workload = Workload {
+    group: "IterKeysValues",
+    tags: &["iteration"],
+    setup: "Insert size elements.",
+    timed: "Iterate over every element along with its key.",
+    requires: Capabilities::NONE,
+};
+
+pub const ITER_HALF: Workload = Workload {
+    group: "IterHalf",
+    tags: &["iteration"],
+    setup: "Insert size elements, remove half of them randomly.",
+    timed: "Iterate over the remaining elements.",
+    requires: Capabilities::NONE,
+};
+
+pub const GET_MUT: Workload = Workload {
+    group: "GetMut",
+    tags: &["lookups", "mutation"],
+    setup: "Insert size elements.",
+    timed: "Get size randomly chosen elements mutably and write to each.",
+    requires: Capabilities::NONE,
+};
+
+pub const GET_STALE: Workload = Workload {
+    group: "GetStale",
+    tags: &["lookups"],
+    setup: "Insert size elements, remove half of them randomly, insert a quarter of size elements \
+            into the freed slots.",
+    timed: "Get size randomly chosen elements, a fraction of them through removed keys.",
+    requires: Capabilities {
+        generational: true,
+        ..Capabilities::NONE
+    },
+};
+
+pub const ITER_MUT: Workload = Workload {
+    group: "IterMut",
+    tags: &["iteration", "mutation"],
+    setup: "Insert size elements.",
+    timed: "Iterate mutably over every element and write to each.",
+    requires: Capabilities {
+        iter_mut: true,
+        ..Capabilities::NONE
+    },
+};
+
+pub const ITER_HALF_MUT: Workload = Workload {
+    group: "IterHalfMut",
+    tags: &["iteration", "mutation"],
+    setup: "Insert size elements, remove half of them randomly.",
+    timed: "Iterate mutably over the remaining elements and write to each.",
+    requires: Capabilities {
+        iter_mut: true,
+        ..Capabilities::NONE
+    },
+};
+
+pub const CHURN: Workload = Workload {
+    group: "Churn",
+    tags: &["mixed"],
+    setup: "Insert size elements, then apply 4 * size operations from the churn mix to fragment \
+            the arena.",
+    timed: "Apply size operations from the churn mix, keeping the length within 10% of size.",
+    requires: Capabilities::NONE,
+};
+
+pub const REPLAY: Workload = Workload {
+    group: "Replay",
+    tags: &["mixed"],
+    setup: "Create an empty arena.",
+    timed: "Replay every operation of the recorded trace.",
+    requires: Capabilities::NONE,
+};
+
+pub const WORKLOADS: [&Workload; 16] = [
+    &INSERT,
+    &INSERT_RESERVED,
+    &INSERT_USED,
+    &REMOVE,
+    &REMOVE_HALF,
+    &RETAIN_HALF,
+    &GET,
+    &GET_MUT,
+    &GET_STALE,
+    &ITER,
+    &ITER_KEYS_VALUES,
+    &ITER_HALF,
+    &ITER_MUT,
+    &ITER_HALF_MUT,
+    &CHURN,
+    &REPLAY,
+];
+
+/// Applies to every workload: how the prepared state reaches the timed routine and how it leaves.
+pub const TIMING: &str = "Each sample clones the prepared arena before timing starts and drops \
+                          it after timing stops, so only the routine itself is measured.";
+
+/// Container sizes swept by every workload in the standard profile.
+pub const SIZES: [usize; 5] = [100, 1_000, 10_000, 100_000, 1_000_000];
+
+/// How long and how widely a run measures, trading precision for time.
+#[derive(Debug)]
+pub struct Profile {
+    pub name: &'static str,
+    /// Samples criterion takes of each benchmark.
+    pub sample_size: usize,
+    pub warm_up: Duration,
+    /// Target time spent taking the samples of each benchmark.
+    pub measurement: Duration,
+    /// Bootstrap resamples criterion uses for its confidence intervals.
+    pub resamples: usize,
+    /// Whether criterion draws its HTML report plots.
+    pub plots: bool,
+    /// Container sizes swept by every workload.
+    pub sizes: &'static [usize],
+}
+
+/// A single small size measured briefly, for checking the whole matrix runs and getting a rough
+/// picture while iterating on an implementation.
+pub const QUICK: Profile = Profile {
+    name: "quick",
+    sample_size: 10,
+    warm_up: Duration::from_millis(1),
+    measurement: Duration::from_millis(5),
+    resamples: 1_000,
+    plots: false,
+    sizes: &[1_000],
+};
+
+/// The default, used for published results.
+pub const STANDARD: Profile = Profile {
+    name: "standard",
+    sample_size: 200,
+    warm_up: Duration::from_secs(3),
+    measurement: Duration::from_secs(5),
+    resamples: 100_000,
+    plots: true,
+    sizes: &SIZES,
+};
+
+/// More and longer samples than standard, for settling close calls.
+pub const THOROUGH: Profile = Profile {
+    name: "thorough",
+    sample_size: 500,
+    warm_up: Duration::from_secs(5),
+    measurement: Duration::from_secs(20),
+    resamples: 200_000,
+    plots: true,
+    sizes: &SIZES,
+};
+
+pub const PROFILES: [&Profile; 3] = [&QUICK, &STANDARD, &THOROUGH];
+
+/// The profile named by the `GENBENCH_PROFILE` environment variable, standard if it is unset.
+pub fn profile() -> &'static Profile {
+    match std::env::var("GENBENCH_PROFILE") {
+        Ok(name) => PROFILES
+            .into_iter()
+            .find(|p| p.name.eq_ignore_ascii_case(&name))
+            .unwrap_or_else(|| {
+                panic!(
+                    "GENBENCH_PROFILE must be quick, standard or thorough, got '{}'",
+                    name
+                )
+            }),
+        Err(_) => &STANDARD,
+    }
+}
+
+/// Sizes whose payload alone would exceed this are skipped for that payload.
+pub const MAX_ARENA_BYTES: usize = 256 << 20;
+
+/// Upper bound on the memory held by one batch of clones.
+const BATCH_BYTES: usize = 64 << 20;
+
+/// The sizes of the selected profile that fit within `MAX_ARENA_BYTES` for payload `T`.
+pub fn sizes<T>() -> impl Iterator<Item = usize> {
+    profile()
+        .sizes
+        .iter()
+        .copied()
+        .filter(|size| size * size_of::<T>() <= MAX_ARENA_BYTES)
+}
+
+/// Fraction of `GetStale` lookups that use a removed key, unless overridden by the
+/// `GENBENCH_STALE_FRACTION` environment variable.
+pub const STALE_FRACTION: f32 = 0.5;
+
+/// The fraction of `GetStale` lookups that use a removed key.
+pub fn stale_fraction() -> f32 {
+    match std::env::var("GENBENCH_STALE_FRACTION") {
+        Ok(value) => match value.parse::<f32>() {
+            Ok(fraction) if (0.0..=1.0).contains(&fraction) => fraction,
+            _ => panic!(
+                "GENBENCH_STALE_FRACTION must be between 0 and 1, got '{}'",
+                value
+            ),
+        },
+        Err(_) => STALE_FRACTION,
+    }
+}
+
+/// Names and tags `GENBENCH_CRATES` can refer to.
+pub fn crate_terms() -> Vec<&'static str> {
+    let names = crate::crates::<()>().into_iter().map(|c| c.name());
+    names.chain(Tag::ALL.iter().map(|t| t.name())).collect()
+}
+
+/// Names and tags `GENBENCH_WORKLOADS` can refer to.
+pub fn workload_terms() -> Vec<&'static str> {
+    let mut terms: Vec<&'static str> = WORKLOADS.iter().map(|w| w.group).collect();
+    for tag in WORKLOADS.iter().flat_map(|w| w.tags) {
+        if !terms.contains(tag) {
+            terms.push(tag);
+        }
+    }
+    terms
+}
+
+/// Implementations to benchmark, from the `GENBENCH_CRATES` environment variable. Everything is
+/// benchmarked when it is unset.
+pub fn crate_selection() -> Selection {
+    selection("GENBENCH_CRATES", &crate_terms())
+}
+
+/// Workloads to benchmark, from the `GENBENCH_WORKLOADS` environment variable. Everything is
+/// benchmarked when it is unset.
+pub fn workload_selection() -> Selection {
+    selection("GENBENCH_WORKLOADS", &workload_terms())
+}
+
+fn selection(var: &str, known: &[&str]) -> Selection {
+    match std::env::var(var) {
+        Ok(spec) => Selection::parse(&spec, known).unwrap_or_else(|e| panic!("{}: {}", var, e)),
+        Err(_) => Selection::all(),
+    }
+}
+
+/// One step of the `Churn` workload. Positions index the list of live keys modulo its length, so
+/// every implementation touches the same elements.
+#[derive(Copy, Clone, Debug)]
+pub enum Op {
+    Get(u32),
+    Insert,
+    Remove(u32),
+}
+
+/// Percentages of each operation in the `Churn` workload, summing to 100.
+#[derive(Copy, Clone, Debug)]
+pub struct Mix {
+    pub get: u32,
+    pub insert: u32,
+    pub remove: u32,
+}
+
+/// Churn mix used unless overridden by the `GENBENCH_CHURN_MIX` environment variable, given as
+/// `get,insert,remove` percentages.
+pub const CHURN_MIX: Mix = Mix {
+    get: 40,
+    insert: 30,
+    remove: 30,
+};
+
+/// Operations applied before timing, per element of size, so fragmentation has built up.
+pub const CHURN_WARMUP: usize = 4;
+
+/// The churn mix, from `GENBENCH_CHURN_MIX` if set.
+pub fn churn_mix() -> Mix {
+    let value = match std::env::var("GENBENCH_CHURN_MIX") {
+        Ok(value) => value,
+        Err(_) => return CHURN_MIX,
+    };
+    let parts: Vec<u32> = value
+        .split(',')
+        .filter_map(|p| p.trim().parse().ok())
+        .collect();
+    match parts[..] {
+        [get, insert, remove] if get + insert + remove == 100 => Mix {
+            get,
+            insert,
+            remove,
+        },
+        _ => panic!(
+            "GENBENCH_CHURN_MIX must be three percentages summing to 100, got '{}'",
+            value
+        ),
+    }
+}
+
+/// Trace replayed unless overridden by the `GENBENCH_TRACE` environment variable.
+pub const TRACE: &str = "traces/example.txt";
+
+/// The trace for the `Replay` workload, in either the binary or the text form.
+pub fn replay_trace() -> Trace {
+    let path = std::env::var("GENBENCH_TRACE").unwrap_or_else(|_| TRACE.to_string());
+    let bytes = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    Trace::parse(&bytes).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+/// Rough footprint of an arena holding `size` values of `T`, including per-slot bookkeeping.
+pub fn footprint<T>(size: usize) -> usize {
+    size * (size_of::<T>() + 16)
+}
+
+/// How a workload's routine is executed.
+pub enum Mode<'a, 'b> {
+    /// Time the routine with criterion.
+    Bench(&'a mut Bencher<'b>),
+    /// Run the routine once, untimed, and record the checksum it returns and its heap usage.
+    Check(&'a mut Outcome),
+}
+
+/// What a `Check` run of a workload observed.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Outcome {
+    pub checksum: u64,
+    /// Only counted when `memory::Counting` is the global allocator.
+    pub memory: Usage,
+}
+
+/// The single place a workload is timed.
+///
+/// `state` is cloned for every iteration outside of the measurement, `routine` is timed, and the
+/// mutated clone is dropped once the batch has been measured. Batches are sized from `footprint`
+/// so large arenas are not cloned hundreds of times up front. The routine returns a checksum of
+/// the work it did, which is compared across implementations by `verify`. Heap usage is only
+/// recorded in `Check` mode, so counting never overlaps with timing.
+#[inline]
+pub fn measure<S: Clone>(
+    mode: Mode,
+    state: &S,
+    footprint: usize,
+    mut routine: impl FnMut(&mut S) -> u64,
+) {
+    match mode {
+        Mode::Bench(b) => {
+            let batch = (BATCH_BYTES / footprint.max(1)).max(1) as u64;
+            b.iter_batched_ref(|| state.clone(), routine, BatchSize::NumIterations(batch))
+        }
+        Mode::Check(outcome) => {
+            let baseline = memory::current();
+            let mut state = state.clone();
+            let tracker = Tracker::start();
+            outcome.checksum = routine(&mut state);
+            outcome.memory = tracker.finish(baseline);
+        }
+    }
+}
+
+/// Runs a workload once against every implementation and panics if any of them did different
+/// work than the others. Returns the heap usage of each run.
+pub fn verify<T: Payload>(
+    group: &str,
+    tests: &[Box<dyn Crate<T>>],
+    run: impl Fn(&dyn Crate<T>, Mode),
+) -> Vec<(&'static str, Usage)> {
+    let outcomes: Vec<(&str, Outcome)> = tests
+        .iter()
+        .map(|test| {
+            let mut outcome = Outcome::default();
+            run(test.as_ref(), Mode::Check(&mut outcome));
+            (test.name(), outcome)
+        })
+        .collect();
+    if outcomes
+        .iter()
+        .all(|(_, o)| o.checksum == outcomes[0].1.checksum)
+    {
+        return outcomes.into_iter().map(|(n, o)| (n, o.memory)).collect();
+    }
+    let mut message = format!("{}: implementations disagree on the work done", group);
+    for (name, outcome) in &outcomes {
+        let _ = write!(message, "\n  {:<24} {}", name, outcome.checksum);
+    }
+    panic!("{}", message);
+}
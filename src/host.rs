@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// The machine results were produced on, as far as it can be told from where the report runs.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Host {
+    pub hostname: Option<String>,
+    pub os: String,
+    pub arch: String,
+    /// Logical CPUs available to the process.
+    pub cpus: usize,
+    /// `model name` of the first processor in `/proc/cpuinfo`.
+    pub cpu_model: Option<String>,
+    /// `cpu MHz` of the first processor in `/proc/cpuinfo`, its clock when the host was read.
+    #[serde(default)]
+    pub cpu_mhz: Option<String>,
+    /// `cache size` of the first processor in `/proc/cpuinfo`.
+    #[serde(default)]
+    pub cache_size: Option<String>,
+    /// cpufreq scaling governor of the first processor, such as `performance`.
+    #[serde(default)]
+    pub governor: Option<String>,
+}
+
+impl Host {
+    pub fn current() -> Host {
+        let cpuinfo = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+        Host {
+            hostname: read_trimmed("/proc/sys/kernel/hostname")
+                .or_else(|| read_trimmed("/etc/hostname")),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpus: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            cpu_model: cpuinfo_field(&cpuinfo, "model name"),
+            cpu_mhz: cpuinfo_field(&cpuinfo, "cpu MHz"),
+            cache_size: cpuinfo_field(&cpuinfo, "cache size"),
+            governor: read_trimmed("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor"),
+        }
+    }
+}
+
+/// Value of the first `key : value` line for `key` in `/proc/cpuinfo` style text.
+pub fn cpuinfo_field(cpuinfo: &str, key: &str) -> Option<String> {
+    cpuinfo.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == key).then(|| value.trim().to_string())
+    })
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    let text = fs::read_to_string(path).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_cpuinfo_field() {
+        let cpuinfo = "processor\t: 0\nvendor_id\t: AuthenticAMD\n\
+                       model name\t: AMD Ryzen 9 3950X 16-Core Processor\n\nprocessor\t: 1\n";
+        assert_eq!(
+            cpuinfo_field(cpuinfo, "model name").as_deref(),
+            Some("AMD Ryzen 9 3950X 16-Core Processor")
+        );
+        assert_eq!(cpuinfo_field(cpuinfo, "flags"), None);
+    }
+}
@@ -1,113 +1,66 @@
-use crate::Crate;
-use criterion::{black_box, BatchSize, Bencher};
-use id_vec::IdVec;
+use crate::{Adapter, Arena, Capabilities, Tag};
+use id_vec::{Id, IdVec};
 
-pub struct CrateIdVec();
-impl Crate for CrateIdVec {
-    fn name(&self) -> &'static str {
-        "IdVec"
+pub type CrateIdVec<T> = Adapter<IdVec<T>>;
+
+impl<T: Clone + 'static> Arena<T> for IdVec<T> {
+    type Key = Id<T>;
+    const NAME: &'static str = "IdVec";
+    const LINK: &'static str = "https://github.com/nical/vodk.rs";
+    const TAGS: &'static [Tag] = &[Tag::UnsafeFree];
+    const CAPABILITIES: Capabilities = Capabilities {
+        generational: false,
+        generational_clear: false,
+        iter_mut: false,
+        with_capacity: true,
+        retain: false,
+    };
+
+    fn new() -> Self {
+        IdVec::new()
+    }
+
+    fn with_capacity(capacity: usize) -> Option<Self> {
+        Some(IdVec::with_capacity(capacity))
+    }
+
+    fn insert(&mut self, value: T) -> Id<T> {
+        self.insert(value)
+    }
+
+    fn remove(&mut self, key: Id<T>) -> bool {
+        // IdVec::remove ignores dead ids without reporting it.
+        let live = self.contains_id(key);
+        self.remove(key);
+        live
+    }
+
+    fn get(&self, key: Id<T>) -> Option<&T> {
+        self.get(key)
     }
 
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: IdVec<usize> = IdVec::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn get_mut(&mut self, key: Id<T>) -> Option<&mut T> {
+        self.get_mut(key)
     }
 
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: IdVec<usize> = IdVec::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for a in 0..size {
-            map.remove(keys[a]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.elements()
     }
 
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: IdVec<usize> = IdVec::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.remove(keys[*a]);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_with_keys(&self) -> impl Iterator<Item = (Id<T>, &T)> {
+        self.iter()
     }
 
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: IdVec<usize> = IdVec::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(keys[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+        // IdVec has no mutable iteration.
+        None::<std::iter::Empty<&mut T>>
     }
 
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: IdVec<usize> = IdVec::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn len(&self) -> usize {
+        self.len()
     }
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: IdVec<usize> = IdVec::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for i in lookup {
-            map.remove(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn clear(&mut self) {
+        self.clear()
     }
 }
@@ -1,121 +1,67 @@
-use crate::Crate;
-use criterion::{black_box, BatchSize, Bencher};
-use indextree::Arena;
+use crate::{Adapter, Arena, Capabilities, Tag};
+use indextree::{Node, NodeId};
 
-pub struct CrateIndextree();
+pub type CrateIndextree<T> = Adapter<indextree::Arena<T>>;
 
-impl Crate for CrateIndextree {
-    fn name(&self) -> &'static str {
-        "indextree"
-    }
-
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let arena = Arena::new();
+impl<T: Clone + 'static> Arena<T> for indextree::Arena<T> {
+    type Key = NodeId;
+    const NAME: &'static str = "indextree";
+    const LINK: &'static str = "https://github.com/saschagrunert/indextree";
+    const TAGS: &'static [Tag] = &[Tag::Tree, Tag::UnsafeFree];
+    const CAPABILITIES: Capabilities = Capabilities {
+        generational: true,
+        generational_clear: false,
+        iter_mut: true,
+        with_capacity: true,
+        retain: false,
+    };
 
-        b.iter_batched(
-            || arena.clone(),
-            |mut arena| {
-                for i in 0..size {
-                    arena.new_node(i);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn new() -> Self {
+        indextree::Arena::new()
     }
 
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut arena = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(arena.new_node(a));
-        }
-        for a in 0..size {
-            keys[a].remove(&mut arena);
-        }
+    fn with_capacity(capacity: usize) -> Option<Self> {
+        Some(indextree::Arena::with_capacity(capacity))
+    }
 
-        b.iter_batched(
-            || arena.clone(),
-            |mut arena| {
-                for i in 0..size {
-                    arena.new_node(i);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn insert(&mut self, value: T) -> NodeId {
+        self.new_node(value)
     }
 
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut arena = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(arena.new_node(a));
+    fn remove(&mut self, key: NodeId) -> bool {
+        if self.get(key).is_none() {
+            return false;
         }
+        key.remove(self);
+        true
+    }
 
-        b.iter_batched(
-            || arena.clone(),
-            |mut arena| {
-                for i in lookup {
-                    keys[*i].remove(&mut arena);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn get(&self, key: NodeId) -> Option<&T> {
+        self.get(key).map(Node::get)
     }
 
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut arena = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(arena.new_node(a));
-        }
+    fn get_mut(&mut self, key: NodeId) -> Option<&mut T> {
+        self.get_mut(key).map(Node::get_mut)
+    }
 
-        b.iter_batched(
-            || arena.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(keys[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        // Removed nodes stay in storage order, so skip them.
+        self.iter().filter_map(Node::try_get)
     }
 
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut arena = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(arena.new_node(a));
-        }
+    fn iter_with_keys(&self) -> impl Iterator<Item = (NodeId, &T)> {
+        self.iter_node_ids().map(move |id| (id, self[id].get()))
+    }
 
-        b.iter_batched_ref(
-            || arena.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+        Some(self.iter_mut().filter_map(Node::try_get_mut))
     }
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut arena = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(arena.new_node(a));
-        }
-        for i in lookup {
-            keys[*i].remove(&mut arena);
-        }
+    fn len(&self) -> usize {
+        self.live_count()
+    }
 
-        b.iter_batched_ref(
-            || arena.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn clear(&mut self) {
+        self.clear()
     }
 }
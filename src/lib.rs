@@ -1,4 +1,17 @@
+pub mod chart;
+pub mod compare;
+pub mod conformance;
+pub mod export;
 pub mod extra;
+pub mod harness;
+pub mod host;
+pub mod memory;
+pub mod metadata;
+pub mod payload;
+pub mod readme;
+pub mod results;
+pub mod select;
+pub mod trace;
 
 pub mod beach_map;
 pub mod bvmap;
@@ -15,32 +28,643 @@ pub mod stable_vec;
 pub mod stash;
 pub mod thunderdome;
 
-use criterion::Bencher;
+use harness::{Mode, Op};
+use std::hint::black_box;
+use std::marker::PhantomData;
+use trace::{Event, Trace};
 
-pub trait Crate {
+pub use payload::Payload;
+
+/// Guarantees an implementation claims, used to decide which conformance checks apply.
+#[derive(Copy, Clone, Debug)]
+pub struct Capabilities {
+    /// A key stays dead after its slot has been reused by a later insert.
+    pub generational: bool,
+    /// Keys from before a `clear` stay dead after their slots have been reused.
+    pub generational_clear: bool,
+    /// `iter_mut` is supported.
+    pub iter_mut: bool,
+    /// `with_capacity` is supported.
+    pub with_capacity: bool,
+    /// `retain` is the implementation's own, rather than the default that removes key by key.
+    pub retain: bool,
+}
+
+impl Capabilities {
+    /// Claims nothing.
+    pub const NONE: Capabilities = Capabilities {
+        generational: false,
+        generational_clear: false,
+        iter_mut: false,
+        with_capacity: false,
+        retain: false,
+    };
+
+    /// True if every guarantee claimed by `required` is also claimed here.
+    pub fn satisfies(&self, required: &Capabilities) -> bool {
+        (self.generational || !required.generational)
+            && (self.generational_clear || !required.generational_clear)
+            && (self.iter_mut || !required.iter_mut)
+            && (self.with_capacity || !required.with_capacity)
+            && (self.retain || !required.retain)
+    }
+}
+
+/// Kinds of implementation, used to pick implementations to benchmark by what they are.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tag {
+    /// Claims `Capabilities::generational`. Given by `Crate::tags`, never listed in `Arena::TAGS`.
+    Generational,
+    /// Values are stored contiguously, without holes.
+    Dense,
+    /// A tree of nodes rather than a flat arena.
+    Tree,
+    /// Tracks which slots are occupied in a bitset.
+    Bitset,
+    /// The library contains no `unsafe` code.
+    UnsafeFree,
+}
+
+impl Tag {
+    pub const ALL: [Tag; 5] = [
+        Tag::Generational,
+        Tag::Dense,
+        Tag::Tree,
+        Tag::Bitset,
+        Tag::UnsafeFree,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tag::Generational => "generational",
+            Tag::Dense => "dense",
+            Tag::Tree => "tree",
+            Tag::Bitset => "bitset",
+            Tag::UnsafeFree => "unsafe-free",
+        }
+    }
+}
+
+/// The minimal container interface every benchmarked library is adapted to.
+pub trait Arena<T: 'static>: Clone {
+    type Key: Copy;
+
+    /// Display name used in benchmark ids and reports.
+    const NAME: &'static str;
+
+    /// Where the library is developed, listed in the README.
+    const LINK: &'static str;
+
+    /// Kinds this implementation belongs to, other than `Tag::Generational`.
+    const TAGS: &'static [Tag];
+
+    const CAPABILITIES: Capabilities;
+
+    fn new() -> Self;
+
+    /// An empty arena with room for `capacity` elements, or None if the library cannot reserve
+    /// capacity up front.
+    fn with_capacity(capacity: usize) -> Option<Self>;
+
+    fn insert(&mut self, value: T) -> Self::Key;
+
+    /// Returns true if the key referred to a live element that has now been removed.
+    fn remove(&mut self, key: Self::Key) -> bool;
+
+    fn get(&self, key: Self::Key) -> Option<&T>;
+
+    fn get_mut(&mut self, key: Self::Key) -> Option<&mut T>;
+
+    fn iter(&self) -> impl Iterator<Item = &T>;
+
+    /// Iterates over the live values along with their keys.
+    fn iter_with_keys(&self) -> impl Iterator<Item = (Self::Key, &T)>;
+
+    /// None if the library has no mutable iteration.
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn clear(&mut self);
+
+    /// Keeps only the values `f` returns true for, visiting each once in an order of the
+    /// implementation's choosing. The default collects the keys of the others in
+    /// `iter_with_keys` order and removes them one at a time; implementations with their own
+    /// override it and claim `Capabilities::retain`.
+    fn retain(&mut self, mut f: impl FnMut(Self::Key, &T) -> bool) {
+        let doomed: Vec<Self::Key> = self
+            .iter_with_keys()
+            .filter(|&(key, value)| !f(key, value))
+            .map(|(key, _)| key)
+            .collect();
+        for key in doomed {
+            self.remove(key);
+        }
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A benchmarkable implementation. Only `Adapter` implements this, so every library is measured
+/// by the same workloads in `harness`.
+pub trait Crate<T: Payload>: sealed::Sealed {
     fn name(&self) -> &'static str;
 
+    fn link(&self) -> &'static str;
+
+    fn tags(&self) -> Vec<Tag>;
+
+    fn capabilities(&self) -> Capabilities;
+
+    /// Runs the conformance suite, returning a description of the first violated guarantee.
+    fn conformance(&self) -> Result<(), String>;
+
     /// Setup: None.
     /// Bench: Insert size elements.
-    fn insert(&self, b: &mut Bencher, size: usize);
+    fn insert(&self, m: Mode, size: usize);
+
+    /// Setup: Reserve capacity for size elements.
+    /// Bench: Insert size elements.
+    fn insert_reserved(&self, m: Mode, size: usize);
 
     /// Setup: Insert size elements, remove size elements.
     /// Bench: Insert size elements.
-    fn reinsert(&self, b: &mut Bencher, size: usize);
+    fn reinsert(&self, m: Mode, size: usize);
 
     /// Setup: Insert size elements.
     /// Bench: Remove size elements.
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize);
+    fn remove(&self, m: Mode, lookup: &[usize], size: usize);
+
+    /// Setup: Insert size elements.
+    /// Bench: Collect the keys of the elements keep rejects, in iteration order, and remove them
+    /// one at a time.
+    fn remove_half(&self, m: Mode, keep: &[bool], size: usize);
+
+    /// Setup: Insert size elements.
+    /// Bench: Retain the elements keep accepts, in iteration order.
+    fn retain_half(&self, m: Mode, keep: &[bool], size: usize);
 
     /// Setup: Insert size elements.
     /// Bench: Get all element indicies from lookup.
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize);
+    fn get(&self, m: Mode, lookup: &[usize], size: usize);
+
+    /// Setup: Insert size elements.
+    /// Bench: Get all element indicies from lookup mutably and write to each.
+    fn get_mut(&self, m: Mode, lookup: &[usize], size: usize);
+
+    /// Setup: Insert size elements. Remove all element indicies from dead, then insert half as
+    /// many new elements so some dead keys point at free slots and some at reused ones.
+    /// Bench: Get all element indicies from lookup, which may include dead ones.
+    fn get_stale(&self, m: Mode, dead: &[usize], lookup: &[usize], size: usize);
 
     /// Setup: Insert size elements.
     /// Bench: Iterate over size elements.
-    fn iterate(&self, b: &mut Bencher, size: usize);
+    fn iterate(&self, m: Mode, size: usize);
+
+    /// Setup: Insert size elements.
+    /// Bench: Iterate over size elements along with their keys.
+    fn iterate_keys_values(&self, m: Mode, size: usize);
 
     /// Setup: Insert size elements. Remove half size elements.
     /// Bench: Iterate over remaining elements.
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize);
+    fn reiterate(&self, m: Mode, lookup: &[usize], size: usize);
+
+    /// Setup: Insert size elements. Apply the warmup operations.
+    /// Bench: Apply the operations.
+    fn churn(&self, m: Mode, warmup: &[Op], ops: &[Op], size: usize);
+
+    /// Setup: None.
+    /// Bench: Replay every event of the trace.
+    fn replay(&self, m: Mode, trace: &Trace);
+
+    /// Setup: Insert size elements.
+    /// Bench: Iterate mutably over size elements and write to each.
+    fn iterate_mut(&self, m: Mode, size: usize);
+
+    /// Setup: Insert size elements. Remove half size elements.
+    /// Bench: Iterate mutably over remaining elements and write to each.
+    fn reiterate_mut(&self, m: Mode, lookup: &[usize], size: usize);
+}
+
+/// Runs the generic workloads against an `Arena` implementation.
+pub struct Adapter<A>(PhantomData<fn() -> A>);
+
+impl<A> Adapter<A> {
+    pub const fn new() -> Adapter<A> {
+        Adapter(PhantomData)
+    }
+}
+
+impl<A> Default for Adapter<A> {
+    fn default() -> Adapter<A> {
+        Adapter::new()
+    }
+}
+
+impl<A> sealed::Sealed for Adapter<A> {}
+
+impl<T: Payload, A: Arena<T>> Crate<T> for Adapter<A> {
+    fn name(&self) -> &'static str {
+        A::NAME
+    }
+
+    fn link(&self) -> &'static str {
+        A::LINK
+    }
+
+    fn tags(&self) -> Vec<Tag> {
+        let generational = A::CAPABILITIES.generational.then_some(Tag::Generational);
+        generational
+            .into_iter()
+            .chain(A::TAGS.iter().copied())
+            .collect()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        A::CAPABILITIES
+    }
+
+    fn conformance(&self) -> Result<(), String> {
+        conformance::check::<T, A>()
+    }
+
+    fn insert(&self, m: Mode, size: usize) {
+        insert::<T, A>(m, size);
+    }
+
+    fn insert_reserved(&self, m: Mode, size: usize) {
+        insert_reserved::<T, A>(m, size);
+    }
+
+    fn reinsert(&self, m: Mode, size: usize) {
+        reinsert::<T, A>(m, size);
+    }
+
+    fn remove(&self, m: Mode, lookup: &[usize], size: usize) {
+        remove::<T, A>(m, lookup, size);
+    }
+
+    fn remove_half(&self, m: Mode, keep: &[bool], size: usize) {
+        remove_half::<T, A>(m, keep, size);
+    }
+
+    fn retain_half(&self, m: Mode, keep: &[bool], size: usize) {
+        retain_half::<T, A>(m, keep, size);
+    }
+
+    fn get(&self, m: Mode, lookup: &[usize], size: usize) {
+        get::<T, A>(m, lookup, size);
+    }
+
+    fn get_mut(&self, m: Mode, lookup: &[usize], size: usize) {
+        get_mut::<T, A>(m, lookup, size);
+    }
+
+    fn get_stale(&self, m: Mode, dead: &[usize], lookup: &[usize], size: usize) {
+        get_stale::<T, A>(m, dead, lookup, size);
+    }
+
+    fn iterate(&self, m: Mode, size: usize) {
+        iterate::<T, A>(m, size);
+    }
+
+    fn iterate_keys_values(&self, m: Mode, size: usize) {
+        iterate_keys_values::<T, A>(m, size);
+    }
+
+    fn reiterate(&self, m: Mode, lookup: &[usize], size: usize) {
+        reiterate::<T, A>(m, lookup, size);
+    }
+
+    fn churn(&self, m: Mode, warmup: &[Op], ops: &[Op], size: usize) {
+        churn::<T, A>(m, warmup, ops, size);
+    }
+
+    fn replay(&self, m: Mode, trace: &Trace) {
+        replay::<T, A>(m, trace);
+    }
+
+    fn iterate_mut(&self, m: Mode, size: usize) {
+        iterate_mut::<T, A>(m, size);
+    }
+
+    fn reiterate_mut(&self, m: Mode, lookup: &[usize], size: usize) {
+        reiterate_mut::<T, A>(m, lookup, size);
+    }
+}
+
+/// Every benchmarked implementation.
+pub fn crates<T: Payload>() -> Vec<Box<dyn Crate<T>>> {
+    vec![
+        Box::new(bvmap::CrateBvMap::<T>::new()),
+        Box::new(stash::CrateStash::<T>::new()),
+        Box::new(stash::CrateUniqueStash::<T>::new()),
+        Box::new(slotmap::CrateSlotMap::<T>::new()),
+        Box::new(slotmap::CrateHopSlotMap::<T>::new()),
+        Box::new(slotmap::CrateDenseSlotMap::<T>::new()),
+        Box::new(slab::CrateSlab::<T>::new()),
+        Box::new(beach_map::CrateBeachMap::<T>::new()),
+        Box::new(stable_vec::CrateExternStableVec::<T>::new()),
+        Box::new(stable_vec::CrateInlineStableVec::<T>::new()),
+        Box::new(id_vec::CrateIdVec::<T>::new()),
+        Box::new(compactmap::CrateCompactMap::<T>::new()),
+        Box::new(generational_arena::CrateGenerationalArena::<T>::new()),
+        Box::new(naive::CrateMooSlotMap::<T>::new()),
+        Box::new(thunderdome::CrateThunderdome::<T>::new()),
+        Box::new(pulz_arena::CratePulzArena::<T>::new()),
+        Box::new(indextree::CrateIndextree::<T>::new()),
+        Box::new(generational_indextree::CrateGenerationalIndextree::<T>::new()),
+    ]
+}
+
+fn fill<T: Payload, A: Arena<T>>(size: usize) -> (A, Vec<A::Key>) {
+    let mut map = A::new();
+    let keys = (0..size).map(|a| map.insert(T::new(a))).collect();
+    (map, keys)
+}
+
+fn insert<T: Payload, A: Arena<T>>(m: Mode, size: usize) {
+    let map = A::new();
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| inserts(i, size));
+}
+
+/// An empty arena that is rebuilt with its capacity whenever it is cloned, since cloning does not
+/// keep spare capacity.
+struct Reserved<T, A> {
+    arena: A,
+    capacity: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static, A: Arena<T>> Reserved<T, A> {
+    fn new(capacity: usize) -> Option<Reserved<T, A>> {
+        Some(Reserved {
+            arena: A::with_capacity(capacity)?,
+            capacity,
+            marker: PhantomData,
+        })
+    }
+}
+
+impl<T: 'static, A: Arena<T>> Clone for Reserved<T, A> {
+    fn clone(&self) -> Self {
+        Reserved::new(self.capacity).unwrap()
+    }
+}
+
+fn insert_reserved<T: Payload, A: Arena<T>>(m: Mode, size: usize) {
+    // Implementations that cannot reserve do no work, and are not benchmarked.
+    let map = match Reserved::<T, A>::new(size) {
+        Some(map) => map,
+        None => return harness::measure(m, &(), 0, |_| 0),
+    };
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| {
+        inserts(&mut i.arena, size)
+    });
+}
+
+fn reinsert<T: Payload, A: Arena<T>>(m: Mode, size: usize) {
+    let (mut map, keys) = fill::<T, A>(size);
+    for key in &keys {
+        map.remove(*key);
+    }
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| inserts(i, size));
+}
+
+/// Inserts size elements, returning how many were inserted. Counted here rather than read back
+/// with `len`, which is not constant time for every implementation.
+fn inserts<T: Payload, A: Arena<T>>(map: &mut A, size: usize) -> u64 {
+    let mut inserted = 0u64;
+    for a in 0..size {
+        map.insert(T::new(a));
+        inserted += 1;
+    }
+    inserted
+}
+
+fn remove<T: Payload, A: Arena<T>>(m: Mode, lookup: &[usize], size: usize) {
+    let (map, keys) = fill::<T, A>(size);
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| {
+        let mut removed = 0;
+        for a in lookup {
+            removed += i.remove(keys[*a]) as u64;
+        }
+        removed
+    });
+}
+
+fn remove_half<T: Payload, A: Arena<T>>(m: Mode, keep: &[bool], size: usize) {
+    let (map, _) = fill::<T, A>(size);
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| {
+        let doomed: Vec<A::Key> = i
+            .iter_with_keys()
+            .zip(keep)
+            .filter(|(_, &keep)| !keep)
+            .map(|((key, _), _)| key)
+            .collect();
+        let rejected = doomed.len() as u64;
+        let mut removed = 0;
+        for key in doomed {
+            removed += i.remove(key) as u64;
+        }
+        rejected + (removed << 32)
+    });
+}
+
+/// Checks the length left rather than the survivors, since implementations that retain in swap
+/// order drop different elements by the same pattern. `len` is constant time for every
+/// implementation with its own `retain`.
+fn retain_half<T: Payload, A: Arena<T>>(m: Mode, keep: &[bool], size: usize) {
+    let (map, _) = fill::<T, A>(size);
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| {
+        let mut keep = keep.iter();
+        let mut visited = 0u64;
+        i.retain(|_, _| {
+            visited += 1;
+            *keep.next().unwrap()
+        });
+        visited + ((i.len() as u64) << 32)
+    });
+}
+
+fn get<T: Payload, A: Arena<T>>(m: Mode, lookup: &[usize], size: usize) {
+    let (map, keys) = fill::<T, A>(size);
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| {
+        let mut sum = 0u64;
+        for a in lookup {
+            if let Some(value) = i.get(keys[*a]) {
+                sum = sum.wrapping_add(value.checksum());
+            }
+        }
+        sum
+    });
+}
+
+fn get_mut<T: Payload, A: Arena<T>>(m: Mode, lookup: &[usize], size: usize) {
+    let (map, keys) = fill::<T, A>(size);
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| {
+        let mut sum = 0u64;
+        for a in lookup {
+            if let Some(value) = i.get_mut(keys[*a]) {
+                value.touch();
+                sum = sum.wrapping_add(value.checksum());
+            }
+        }
+        sum
+    });
+}
+
+fn get_stale<T: Payload, A: Arena<T>>(m: Mode, dead: &[usize], lookup: &[usize], size: usize) {
+    let (mut map, keys) = fill::<T, A>(size);
+    for a in dead {
+        map.remove(keys[*a]);
+    }
+    for a in 0..dead.len() / 2 {
+        map.insert(T::new(size + a));
+    }
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| {
+        let mut sum = 0u64;
+        for a in lookup {
+            if let Some(value) = i.get(keys[*a]) {
+                sum = sum.wrapping_add(value.checksum());
+            }
+        }
+        sum
+    });
+}
+
+fn iterate<T: Payload, A: Arena<T>>(m: Mode, size: usize) {
+    let (map, _) = fill::<T, A>(size);
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| {
+        let mut sum = 0u64;
+        for value in i.iter() {
+            sum = sum.wrapping_add(value.checksum());
+        }
+        sum
+    });
+}
+
+fn iterate_keys_values<T: Payload, A: Arena<T>>(m: Mode, size: usize) {
+    let (map, _) = fill::<T, A>(size);
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| {
+        let mut sum = 0u64;
+        for (key, value) in i.iter_with_keys() {
+            // The key is not folded into the checksum since key types differ, but it must still
+            // be produced.
+            black_box(key);
+            sum = sum.wrapping_add(value.checksum());
+        }
+        sum
+    });
+}
+
+fn reiterate<T: Payload, A: Arena<T>>(m: Mode, lookup: &[usize], size: usize) {
+    let (mut map, keys) = fill::<T, A>(size);
+    for a in lookup {
+        map.remove(keys[*a]);
+    }
+    harness::measure(m, &map, harness::footprint::<T>(size), |i| {
+        let mut sum = 0u64;
+        for value in i.iter() {
+            sum = sum.wrapping_add(value.checksum());
+        }
+        sum
+    });
+}
+
+fn iterate_mut<T: Payload, A: Arena<T>>(m: Mode, size: usize) {
+    let (map, _) = fill::<T, A>(size);
+    harness::measure(m, &map, harness::footprint::<T>(size), write_all);
+}
+
+fn reiterate_mut<T: Payload, A: Arena<T>>(m: Mode, lookup: &[usize], size: usize) {
+    let (mut map, keys) = fill::<T, A>(size);
+    for a in lookup {
+        map.remove(keys[*a]);
+    }
+    harness::measure(m, &map, harness::footprint::<T>(size), write_all);
+}
+
+/// Writes to every element through `iter_mut`, or does nothing if it is unsupported.
+fn write_all<T: Payload, A: Arena<T>>(map: &mut A) -> u64 {
+    let mut sum = 0u64;
+    if let Some(values) = map.iter_mut() {
+        for value in values {
+            value.touch();
+            sum = sum.wrapping_add(value.checksum());
+        }
+    }
+    sum
+}
+
+fn churn<T: Payload, A: Arena<T>>(m: Mode, warmup: &[Op], ops: &[Op], size: usize) {
+    let (mut map, mut keys) = fill::<T, A>(size);
+    apply(&mut map, &mut keys, warmup);
+    harness::measure(
+        m,
+        &(map, keys),
+        harness::footprint::<T>(size),
+        |(map, keys)| apply(map, keys, ops),
+    );
+}
+
+/// Applies churn operations, keeping `keys` as the list of live keys, whose length stands in for
+/// `len` since that is not constant time for every implementation.
+fn apply<T: Payload, A: Arena<T>>(map: &mut A, keys: &mut Vec<A::Key>, ops: &[Op]) -> u64 {
+    let mut sum = 0u64;
+    for (a, op) in ops.iter().enumerate() {
+        match *op {
+            Op::Get(r) => {
+                if let Some(value) = map.get(keys[r as usize % keys.len()]) {
+                    sum = sum.wrapping_add(value.checksum());
+                }
+            }
+            Op::Insert => keys.push(map.insert(T::new(a))),
+            Op::Remove(r) => {
+                let key = keys.swap_remove(r as usize % keys.len());
+                sum = sum.wrapping_add(map.remove(key) as u64);
+            }
+        }
+    }
+    sum.wrapping_add(keys.len() as u64)
+}
+
+fn replay<T: Payload, A: Arena<T>>(m: Mode, trace: &Trace) {
+    // Sized up front so the timed region never grows the handle table.
+    let keys: Vec<Option<A::Key>> = vec![None; trace.handles()];
+    let footprint = harness::footprint::<T>(trace.handles());
+    harness::measure(m, &(A::new(), keys), footprint, |(map, keys)| {
+        let mut sum = 0u64;
+        let mut inserted = 0;
+        for event in trace.events() {
+            match *event {
+                Event::Insert => {
+                    keys[inserted] = Some(map.insert(T::new(inserted)));
+                    inserted += 1;
+                }
+                Event::Remove(h) => {
+                    if let Some(key) = keys[h as usize] {
+                        sum = sum.wrapping_add(map.remove(key) as u64);
+                    }
+                }
+                Event::Get(h) => {
+                    if let Some(value) = keys[h as usize].and_then(|key| map.get(key)) {
+                        sum = sum.wrapping_add(value.checksum());
+                    }
+                }
+                Event::Iterate => {
+                    for value in map.iter() {
+                        sum = sum.wrapping_add(value.checksum());
+                    }
+                }
+            }
+        }
+        sum
+    });
 }
@@ -0,0 +1,552 @@
+use genbench::chart::{Chart, Scale, Series, PALETTE};
+use genbench::compare;
+use genbench::export::Export;
+use genbench::harness;
+use genbench::memory::{self, Record};
+use genbench::metadata::{self, Metadata};
+use genbench::readme::{self as sections, replace_section};
+use genbench::results::{self, Measurement};
+use genbench::select::Selection;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Where `charts` writes the images the README links to.
+const IMAGES_DIR: &str = "images";
+const README: &str = "README.md";
+const CHART_PAYLOAD: &str = "u32";
+const CHART_SIZE: usize = 10_000;
+
+/// Implementations left out of the linear charts because they would flatten everyone else.
+const OUTLIERS: [&str; 1] = ["IdVec"];
+
+/// Percent slowdown `compare` tolerates by default.
+const THRESHOLD: f64 = 5.0;
+
+const USAGE: &str = "\
+Usage: genbench <command> [options]
+       genbench compare <baseline.json> <current.json> [options]
+
+Commands:
+  sizes          Print ns per element for every implementation at every benchmarked size.
+  memory         Print heap usage for every implementation at every benchmarked size.
+  allocations    Print allocations per insert with and without reserved capacity.
+  charts         Write SVG bar charts of every workload for one payload and size.
+  readme         Regenerate the crate, procedure and results tables in the README.
+  export         Print every result with host metadata as JSON or CSV.
+  compare        Print the change between two JSON exports, failing on regressions or
+                 on exports from different benchmark profiles.
+  list, --list   Print the implementations and workloads a benchmark run would select.
+
+Options:
+  --criterion <dir>    Criterion output directory [default: target/criterion]
+  --memory <dir>       Memory records written by the bench [default: target/genbench/memory]
+  --metadata <file>    Host and build of the run [default: target/genbench/metadata.json]
+  --images <dir>       Where charts are written [default: images]
+  --readme <file>      README to regenerate [default: README.md]
+  --payload <name>     Payload charted and tabulated [default: u32]
+  --size <n>           Size charted and tabulated [default: 10000]
+  --format <format>    Export format, json or csv [default: json]
+  --threshold <pct>    Slowdown compare fails on, in percent [default: 5]
+  --implementation <name>
+                       Only fail compare on this implementation; may be repeated
+  --crates <terms>     Implementations to list [default: $GENBENCH_CRATES]
+  --workloads <terms>  Workloads to list [default: $GENBENCH_WORKLOADS]
+
+Terms are names or tags separated by commas, and a leading - excludes, as in generational,-IdVec.";
+
+struct Options {
+    criterion: PathBuf,
+    memory: PathBuf,
+    metadata: PathBuf,
+    images: PathBuf,
+    readme: PathBuf,
+    payload: String,
+    size: usize,
+    format: String,
+    threshold: f64,
+    implementations: Vec<String>,
+    inputs: Vec<PathBuf>,
+    crates: Option<String>,
+    workloads: Option<String>,
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let mut command = None;
+    let mut options = Options {
+        criterion: PathBuf::from(results::CRITERION_DIR),
+        memory: PathBuf::from(memory::MEMORY_DIR),
+        metadata: PathBuf::from(metadata::METADATA_FILE),
+        images: PathBuf::from(IMAGES_DIR),
+        readme: PathBuf::from(README),
+        payload: CHART_PAYLOAD.to_string(),
+        size: CHART_SIZE,
+        format: "json".to_string(),
+        threshold: THRESHOLD,
+        implementations: Vec::new(),
+        inputs: Vec::new(),
+        crates: None,
+        workloads: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--criterion" | "--memory" | "--metadata" | "--images" | "--readme" | "--payload"
+            | "--size" | "--format" | "--threshold" | "--implementation" | "--crates"
+            | "--workloads" => {}
+            "--list" if command.is_none() => {
+                command = Some("list".to_string());
+                continue;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ if command.is_none() => {
+                command = Some(arg);
+                continue;
+            }
+            _ => {
+                options.inputs.push(PathBuf::from(arg));
+                continue;
+            }
+        }
+        let value = match args.next() {
+            Some(value) => value,
+            None => return usage(&format!("{} needs a value", arg)),
+        };
+        match arg.as_str() {
+            "--criterion" => options.criterion = PathBuf::from(value),
+            "--memory" => options.memory = PathBuf::from(value),
+            "--metadata" => options.metadata = PathBuf::from(value),
+            "--images" => options.images = PathBuf::from(value),
+            "--readme" => options.readme = PathBuf::from(value),
+            "--payload" => options.payload = value,
+            "--format" => options.format = value,
+            "--implementation" => options.implementations.push(value),
+            "--crates" => options.crates = Some(value),
+            "--workloads" => options.workloads = Some(value),
+            "--threshold" => match value.parse() {
+                Ok(threshold) => options.threshold = threshold,
+                Err(_) => return usage(&format!("bad threshold '{}'", value)),
+            },
+            _ => match value.parse() {
+                Ok(size) => options.size = size,
+                Err(_) => return usage(&format!("bad size '{}'", value)),
+            },
+        }
+    }
+    let report: fn(&Options) -> Result<(), String> = match command.as_deref() {
+        Some("sizes") => sizes,
+        Some("memory") => memory,
+        Some("allocations") => allocations,
+        Some("charts") => charts,
+        Some("readme") => readme,
+        Some("export") => export,
+        Some("compare") => compare,
+        Some("list") => list,
+        Some(other) => return usage(&format!("unknown command '{}'", other)),
+        None => return usage("missing command"),
+    };
+    if command.as_deref() != Some("compare") {
+        if let Some(input) = options.inputs.first() {
+            return usage(&format!("unexpected argument '{}'", input.display()));
+        }
+    }
+    match report(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn usage(error: &str) -> ExitCode {
+    eprintln!("error: {}\n\n{}", error, USAGE);
+    ExitCode::FAILURE
+}
+
+/// The metadata the bench saved for its run, or this host and build if there is none.
+fn run_metadata(options: &Options) -> Metadata {
+    Metadata::load(&options.metadata).unwrap_or_else(|e| {
+        eprintln!("warning: no run metadata ({}), describing this host", e);
+        // The profile of this invocation says nothing about the run being described.
+        Metadata {
+            bench_profile: String::new(),
+            ..Metadata::current()
+        }
+    })
+}
+
+/// One Markdown table per workload and payload, with a column per size. Cells are the lower bound
+/// of the mean's 95% confidence interval divided by the elements touched; the fastest
+/// implementation at each size is in bold.
+fn sizes(options: &Options) -> Result<(), String> {
+    let measurements = results::load(&options.criterion)?;
+    println!("Measured on {}\n", run_metadata(options).summary());
+    let mut groups: BTreeMap<(&str, &str), Vec<&Measurement>> = BTreeMap::new();
+    for m in &measurements {
+        groups.entry((&m.workload, &m.payload)).or_default().push(m);
+    }
+    for ((workload, payload), group) in groups {
+        let mut sizes: Vec<usize> = group.iter().map(|m| m.size).collect();
+        sizes.sort_unstable();
+        sizes.dedup();
+        let mut rows: BTreeMap<&str, BTreeMap<usize, f64>> = BTreeMap::new();
+        for m in &group {
+            rows.entry(&m.implementation)
+                .or_default()
+                .insert(m.size, m.per_element(m.mean.lower));
+        }
+        let fastest: Vec<f64> = sizes
+            .iter()
+            .map(|size| {
+                rows.values()
+                    .filter_map(|row| row.get(size).copied())
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .collect();
+
+        println!("## {}/{} (ns per element)\n", workload, payload);
+        print!("| Implementation |");
+        for size in &sizes {
+            print!(" {} |", size);
+        }
+        print!("\n|---|");
+        for _ in &sizes {
+            print!("---:|");
+        }
+        println!();
+        for (implementation, row) in &rows {
+            print!("| {} |", implementation);
+            for (size, fastest) in sizes.iter().zip(&fastest) {
+                match row.get(size) {
+                    Some(ns) if ns == fastest => print!(" **{:.2}** |", ns),
+                    Some(ns) => print!(" {:.2} |", ns),
+                    None => print!(" |"),
+                }
+            }
+            println!();
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// One Markdown table per workload and payload with a row per size and implementation, taken from
+/// the untimed check run of each benchmark. Byte counts are per element of size.
+fn memory(options: &Options) -> Result<(), String> {
+    let records = memory::load(&options.memory)?;
+    let mut groups: BTreeMap<(&str, &str), Vec<&Record>> = BTreeMap::new();
+    for r in &records {
+        groups.entry((&r.workload, &r.payload)).or_default().push(r);
+    }
+    for ((workload, payload), mut group) in groups {
+        // Stable, so implementations keep their registry order within a size.
+        group.sort_by_key(|r| r.size);
+        println!("## {}/{} (heap)\n", workload, payload);
+        println!(
+            "| Size | Implementation | Resident bytes/elem | Peak bytes/elem | Allocations | Reallocations |"
+        );
+        println!("|---:|---|---:|---:|---:|---:|");
+        for r in group {
+            let per_element = |bytes: u64| bytes as f64 / r.size.max(1) as f64;
+            println!(
+                "| {} | {} | {:.1} | {:.1} | {} | {} |",
+                r.size,
+                r.implementation,
+                per_element(r.usage.resident_bytes),
+                per_element(r.usage.peak_bytes),
+                r.usage.allocations,
+                r.usage.reallocations
+            );
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// One Markdown table per payload comparing `Insert` with `InsertReserved`. Cells are the
+/// allocations plus reallocations made while inserting, divided by the number of inserts, as
+/// `unreserved / reserved`.
+fn allocations(options: &Options) -> Result<(), String> {
+    let records = memory::load(&options.memory)?;
+    let mut payloads: Vec<&str> = Vec::new();
+    let mut implementations: Vec<&str> = Vec::new();
+    let mut sizes: Vec<usize> = Vec::new();
+    let mut cells: BTreeMap<(&str, &str, usize, bool), f64> = BTreeMap::new();
+    // Unreserved first, so every implementation is listed in registry order.
+    let insert = records.iter().filter(|r| r.workload == "Insert");
+    let reserved = records.iter().filter(|r| r.workload == "InsertReserved");
+    for (r, reserved) in insert
+        .map(|r| (r, false))
+        .chain(reserved.map(|r| (r, true)))
+    {
+        if !payloads.contains(&r.payload.as_str()) {
+            payloads.push(&r.payload);
+        }
+        if !implementations.contains(&r.implementation.as_str()) {
+            implementations.push(&r.implementation);
+        }
+        if !sizes.contains(&r.size) {
+            sizes.push(r.size);
+        }
+        let per_insert = (r.usage.allocations + r.usage.reallocations) as f64 / r.size as f64;
+        cells.insert(
+            (&r.payload, &r.implementation, r.size, reserved),
+            per_insert,
+        );
+    }
+    sizes.sort_unstable();
+    let cell = |key| match cells.get(&key) {
+        Some(n) => format!("{:.5}", n),
+        None => "-".to_string(),
+    };
+    for payload in payloads {
+        let sizes: Vec<usize> = sizes
+            .iter()
+            .copied()
+            .filter(|&size| cells.keys().any(|k| k.0 == payload && k.2 == size))
+            .collect();
+        println!(
+            "## Insert/{} (allocations per insert, unreserved / reserved)\n",
+            payload
+        );
+        print!("| Implementation |");
+        for size in &sizes {
+            print!(" {} |", size);
+        }
+        print!("\n|---|");
+        for _ in &sizes {
+            print!("---:|");
+        }
+        println!();
+        for implementation in &implementations {
+            print!("| {} |", implementation);
+            for &size in &sizes {
+                print!(
+                    " {} / {} |",
+                    cell((payload, implementation, size, false)),
+                    cell((payload, implementation, size, true))
+                );
+            }
+            println!();
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// Grouped bar charts of every workload benchmarked at one payload and size, in ns per element
+/// using the lower bound of the mean's 95% confidence interval:
+/// - `bench_all_log.svg`: every implementation on a log scale.
+/// - `bench_all_linear.svg`: every implementation except the outliers on a linear scale.
+/// - `bench_gen_linear.svg`: generational implementations except the outliers on a linear scale.
+fn charts(options: &Options) -> Result<(), String> {
+    let measurements = results::load(&options.criterion)?;
+    let charted: Vec<&Measurement> = measurements
+        .iter()
+        .filter(|m| m.payload == options.payload && m.size == options.size)
+        .collect();
+    if charted.is_empty() {
+        return Err(format!(
+            "no results for payload {} at size {} in {}",
+            options.payload,
+            options.size,
+            options.criterion.display()
+        ));
+    }
+    let groups: Vec<&str> = harness::WORKLOADS
+        .iter()
+        .map(|w| w.group)
+        .filter(|group| charted.iter().any(|m| m.workload == *group))
+        .collect();
+    // Registry order fixes every implementation's color across charts.
+    let registry = genbench::crates::<u32>();
+    let series = |keep: &dyn Fn(&str) -> bool| -> Vec<Series> {
+        registry
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| keep(c.name()))
+            .map(|(i, c)| Series {
+                name: c.name().to_string(),
+                color: PALETTE[i % PALETTE.len()],
+                values: groups
+                    .iter()
+                    .map(|group| {
+                        charted
+                            .iter()
+                            .find(|m| m.workload == *group && m.implementation == c.name())
+                            .map(|m| m.per_element(m.mean.lower))
+                    })
+                    .collect(),
+            })
+            .filter(|s| s.values.iter().any(Option::is_some))
+            .collect()
+    };
+    let generational: Vec<&str> = registry
+        .iter()
+        .filter(|c| c.capabilities().generational)
+        .map(|c| c.name())
+        .collect();
+    let charts = [
+        ("bench_all_log.svg", "All", Scale::Log, series(&|_| true)),
+        (
+            "bench_all_linear.svg",
+            "All",
+            Scale::Linear,
+            series(&|name| !OUTLIERS.contains(&name)),
+        ),
+        (
+            "bench_gen_linear.svg",
+            "Generational arenas",
+            Scale::Linear,
+            series(&|name| generational.contains(&name) && !OUTLIERS.contains(&name)),
+        ),
+    ];
+    fs::create_dir_all(&options.images)
+        .map_err(|e| format!("{}: {}", options.images.display(), e))?;
+    for (file, title, scale, series) in charts {
+        let chart = Chart {
+            title: format!("{}, {} x {}", title, options.payload, options.size),
+            unit: "ns per element (lower bound of 95% CI)".to_string(),
+            scale,
+            groups: groups.iter().map(|group| group.to_string()).collect(),
+            series,
+        };
+        let path = options.images.join(file);
+        fs::write(&path, chart.to_svg()).map_err(|e| format!("{}: {}", path.display(), e))?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/// Rewrites the generated sections of the README in place: the implementation list, the workload
+/// procedures and the results at one payload and size.
+fn readme(options: &Options) -> Result<(), String> {
+    let measurements = results::load(&options.criterion)?;
+    let mut results = sections::results(&measurements, &options.payload, options.size);
+    if results.is_empty() {
+        return Err(format!(
+            "no results for payload {} at size {} in {}",
+            options.payload,
+            options.size,
+            options.criterion.display()
+        ));
+    }
+    results.insert_str(
+        0,
+        &format!("Measured on {}.\n\n", run_metadata(options).summary()),
+    );
+    let path = &options.readme;
+    let mut doc = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let generated = [
+        ("crates", sections::crates(&genbench::crates::<u32>())),
+        ("procedure", sections::procedure(&harness::WORKLOADS)),
+        ("results", results),
+    ];
+    for (name, body) in generated {
+        doc =
+            replace_section(&doc, name, &body).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    fs::write(path, doc).map_err(|e| format!("{}: {}", path.display(), e))?;
+    println!("wrote {}", path.display());
+    Ok(())
+}
+
+/// Every result criterion has on disk, along with the host running the export, in the stable
+/// schema of `genbench::export`.
+fn export(options: &Options) -> Result<(), String> {
+    let measurements = results::load(&options.criterion)?;
+    let export = Export::new(run_metadata(options), &measurements);
+    match options.format.as_str() {
+        "json" => println!("{}", export.to_json()),
+        "csv" => print!("{}", export.to_csv()),
+        other => return Err(format!("unknown export format '{}'", other)),
+    }
+    Ok(())
+}
+
+/// A delta table between two exports. Fails when a chosen implementation, or any when none were
+/// chosen, is significantly slower than the baseline by more than the threshold.
+fn compare(options: &Options) -> Result<(), String> {
+    let (baseline, current) = match options.inputs.as_slice() {
+        [baseline, current] => (baseline, current),
+        _ => return Err("compare needs a baseline and a current export".to_string()),
+    };
+    let read = |path: &PathBuf| -> Result<Export, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))
+    };
+    let (baseline, current) = (read(baseline)?, read(current)?);
+    compare::check_profiles(&baseline, &current)?;
+    for (name, export) in [("baseline", &baseline), ("current", &current)] {
+        if export.metadata.bench_profile.is_empty() {
+            eprintln!(
+                "warning: the {} export does not record its benchmark profile",
+                name
+            );
+        }
+    }
+    let comparison = compare::compare(&baseline, &current);
+    print!("{}", comparison.to_markdown(options.threshold));
+    let regressions: Vec<String> = comparison
+        .deltas
+        .iter()
+        .filter(|d| d.regressed(options.threshold))
+        .filter(|d| {
+            options.implementations.is_empty()
+                || options.implementations.contains(&d.implementation)
+        })
+        .map(|d| {
+            format!(
+                "{}/{}/{}/{}",
+                d.workload, d.payload, d.implementation, d.size
+            )
+        })
+        .collect();
+    if regressions.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "{} regressed by more than {}%: {}",
+        regressions.len(),
+        options.threshold,
+        regressions.join(", ")
+    ))
+}
+
+/// The implementations and workloads selected by `--crates` and `--workloads`, or by the
+/// environment variables the bench reads, with their tags.
+fn list(options: &Options) -> Result<(), String> {
+    let pick = |spec: &Option<String>, known: &[&str], env: fn() -> Selection| match spec {
+        Some(spec) => Selection::parse(spec, known),
+        None => Ok(env()),
+    };
+    let crates = pick(
+        &options.crates,
+        &harness::crate_terms(),
+        harness::crate_selection,
+    )?;
+    let workloads = pick(
+        &options.workloads,
+        &harness::workload_terms(),
+        harness::workload_selection,
+    )?;
+    println!("Implementations:");
+    for c in genbench::crates::<u32>() {
+        if crates.picks_crate(c.as_ref()) {
+            let tags: Vec<&str> = c.tags().iter().map(|t| t.name()).collect();
+            println!("  {:<24}{}", c.name(), tags.join(", "));
+        }
+    }
+    println!("\nWorkloads:");
+    for w in harness::WORKLOADS {
+        if workloads.picks_workload(w) {
+            println!("  {:<24}{}", w.group, w.tags.join(", "));
+        }
+    }
+    Ok(())
+}
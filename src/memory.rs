@@ -0,0 +1,184 @@
+use serde::{Deserialize, Serialize};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+/// Where the bench writes memory records when run from the crate root.
+pub const MEMORY_DIR: &str = "target/genbench/memory";
+
+static CURRENT: AtomicU64 = AtomicU64::new(0);
+static PEAK: AtomicU64 = AtomicU64::new(0);
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static REALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// A global allocator that forwards to the system allocator and counts what passes through it.
+/// Nothing is counted unless it is registered with `#[global_allocator]`.
+pub struct Counting;
+
+fn grow(bytes: usize) {
+    let now = CURRENT.fetch_add(bytes as u64, Relaxed) + bytes as u64;
+    PEAK.fetch_max(now, Relaxed);
+}
+
+fn shrink(bytes: usize) {
+    CURRENT.fetch_sub(bytes as u64, Relaxed);
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Relaxed);
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            ALLOCATIONS.fetch_add(1, Relaxed);
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        shrink(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() {
+            REALLOCATIONS.fetch_add(1, Relaxed);
+            if new_size > layout.size() {
+                grow(new_size - layout.size());
+            } else {
+                shrink(layout.size() - new_size);
+            }
+        }
+        new
+    }
+}
+
+/// Heap activity of one run of a workload routine.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Usage {
+    pub allocations: u64,
+    pub reallocations: u64,
+    /// Highest heap use while the routine ran, including the state it started from.
+    pub peak_bytes: u64,
+    /// Heap held by the state once the routine finished.
+    pub resident_bytes: u64,
+}
+
+/// Heap bytes currently allocated.
+pub fn current() -> u64 {
+    CURRENT.load(Relaxed)
+}
+
+/// Counts heap activity from the moment it is created.
+pub struct Tracker {
+    allocations: u64,
+    reallocations: u64,
+}
+
+impl Tracker {
+    /// Starts counting. Peak usage is measured from here on.
+    pub fn start() -> Tracker {
+        PEAK.store(CURRENT.load(Relaxed), Relaxed);
+        Tracker {
+            allocations: ALLOCATIONS.load(Relaxed),
+            reallocations: REALLOCATIONS.load(Relaxed),
+        }
+    }
+
+    /// Usage since `start`, with byte counts relative to `baseline`, the heap use before the
+    /// measured state was built.
+    pub fn finish(self, baseline: u64) -> Usage {
+        Usage {
+            allocations: ALLOCATIONS.load(Relaxed) - self.allocations,
+            reallocations: REALLOCATIONS.load(Relaxed) - self.reallocations,
+            peak_bytes: PEAK.load(Relaxed).saturating_sub(baseline),
+            resident_bytes: CURRENT.load(Relaxed).saturating_sub(baseline),
+        }
+    }
+}
+
+/// Memory used by one implementation on one workload, payload and size.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub workload: String,
+    pub payload: String,
+    pub implementation: String,
+    pub size: usize,
+    #[serde(flatten)]
+    pub usage: Usage,
+}
+
+/// Writes the records of one workload, payload and size to a file of their own in `dir`.
+pub fn save(dir: &Path, records: &[Record]) -> Result<(), String> {
+    let first = match records.first() {
+        Some(first) => first,
+        None => return Ok(()),
+    };
+    let name = format!("{}_{}_{}.json", first.workload, first.payload, first.size);
+    let json = serde_json::to_string_pretty(records).map_err(|e| e.to_string())?;
+    fs::create_dir_all(dir)
+        .and_then(|_| fs::write(dir.join(name), json))
+        .map_err(|e| format!("{}: {}", dir.display(), e))
+}
+
+/// Reads every record saved in `dir`.
+pub fn load(dir: &Path) -> Result<Vec<Record>, String> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| format!("{}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    paths.sort();
+    let mut records = Vec::new();
+    for path in paths {
+        let json = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut saved: Vec<Record> =
+            serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+        records.append(&mut saved);
+    }
+    Ok(records)
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("genbench-memory-{}", std::process::id()));
+        let records: Vec<Record> = ["SlotMap", "Slab"]
+            .iter()
+            .enumerate()
+            .map(|(i, implementation)| Record {
+                workload: "Insert".to_string(),
+                payload: "u32".to_string(),
+                implementation: implementation.to_string(),
+                size: 1000,
+                usage: Usage {
+                    allocations: i as u64 + 1,
+                    reallocations: 10,
+                    peak_bytes: 8192,
+                    resident_bytes: 4096,
+                },
+            })
+            .collect();
+        save(&dir, &records).unwrap();
+        let loaded = load(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded, Ok(records));
+    }
+}
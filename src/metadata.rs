@@ -0,0 +1,217 @@
+use crate::harness;
+use crate::host::Host;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Where the bench writes the metadata of its run when run from the crate root.
+pub const METADATA_FILE: &str = "target/genbench/metadata.json";
+
+/// What a set of results was produced with.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metadata {
+    pub host: Host,
+    #[serde(default)]
+    pub build: Build,
+    /// Resolved version of every direct dependency, from `Cargo.lock`.
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+    /// `GENBENCH_PROFILE` the benchmarks ran with, empty for runs from before it was recorded.
+    #[serde(default)]
+    pub bench_profile: String,
+}
+
+/// Toolchain and profile the benchmarks were compiled with, captured by `build.rs`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Build {
+    pub rustc: String,
+    pub cargo: String,
+    /// `release` for `cargo bench`, `debug` for `cargo test`.
+    pub profile: String,
+    pub opt_level: String,
+    pub debug: String,
+    pub target: String,
+    pub target_features: Vec<String>,
+}
+
+impl Build {
+    pub fn current() -> Build {
+        Build {
+            rustc: env!("GENBENCH_BUILD_RUSTC").to_string(),
+            cargo: env!("GENBENCH_BUILD_CARGO").to_string(),
+            profile: env!("GENBENCH_BUILD_PROFILE").to_string(),
+            opt_level: env!("GENBENCH_BUILD_OPT_LEVEL").to_string(),
+            debug: env!("GENBENCH_BUILD_DEBUG").to_string(),
+            target: env!("GENBENCH_BUILD_TARGET").to_string(),
+            target_features: env!("GENBENCH_BUILD_CARGO_CFG_TARGET_FEATURE")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl Metadata {
+    /// This host, build and `GENBENCH_PROFILE`, with dependency versions from the crate's
+    /// `Cargo.lock` if it can be read.
+    pub fn current() -> Metadata {
+        let lock = Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.lock");
+        Metadata {
+            host: Host::current(),
+            build: Build::current(),
+            dependencies: fs::read_to_string(lock)
+                .map(|lock| dependencies(&lock, env!("CARGO_PKG_NAME")))
+                .unwrap_or_default(),
+            bench_profile: harness::profile().name.to_string(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(path, json))
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<Metadata, String> {
+        let json = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// One line describing the host, build and benchmark profile, for the top of reports.
+    pub fn summary(&self) -> String {
+        let unknown = || "unknown CPU".to_string();
+        let mut summary = format!(
+            "{} ({} CPUs",
+            self.host.cpu_model.clone().unwrap_or_else(unknown),
+            self.host.cpus
+        );
+        if let Some(governor) = &self.host.governor {
+            summary.push_str(&format!(", {} governor", governor));
+        }
+        summary.push_str(&format!(
+            "), {}, {} profile at opt-level {}",
+            self.build.rustc, self.build.profile, self.build.opt_level
+        ));
+        if !self.bench_profile.is_empty() {
+            summary.push_str(&format!(", {} benchmark profile", self.bench_profile));
+        }
+        summary
+    }
+}
+
+/// Versions `Cargo.lock` resolved for the dependencies of `package`.
+pub fn dependencies(lock: &str, package: &str) -> BTreeMap<String, String> {
+    let packages: Vec<Package> = lock
+        .split("[[package]]")
+        .skip(1)
+        .map(Package::parse)
+        .collect();
+    let root = match packages.iter().find(|p| p.name == package) {
+        Some(root) => root,
+        None => return BTreeMap::new(),
+    };
+    root.dependencies
+        .iter()
+        .filter_map(|dependency| {
+            // Entries carry a version only when several versions of the crate are locked.
+            let mut words = dependency.split_whitespace();
+            let name = words.next()?;
+            let version = match words.next() {
+                Some(version) => version.to_string(),
+                None => packages.iter().find(|p| p.name == name)?.version.clone(),
+            };
+            Some((name.to_string(), version))
+        })
+        .collect()
+}
+
+/// The parts of a `[[package]]` entry of `Cargo.lock` needed to resolve versions.
+#[derive(Default)]
+struct Package {
+    name: String,
+    version: String,
+    dependencies: Vec<String>,
+}
+
+impl Package {
+    fn parse(entry: &str) -> Package {
+        let mut package = Package::default();
+        let mut lines = entry.lines().map(str::trim);
+        while let Some(line) = lines.next() {
+            let (key, value) = match line.split_once(" = ") {
+                Some(split) => split,
+                None => continue,
+            };
+            match key {
+                "name" => package.name = value.trim_matches('"').to_string(),
+                "version" => package.version = value.trim_matches('"').to_string(),
+                "dependencies" if value == "[" => {
+                    package.dependencies = lines
+                        .by_ref()
+                        .take_while(|line| *line != "]")
+                        .map(|line| line.trim_end_matches(',').trim_matches('"').to_string())
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+        package
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_direct_dependencies() {
+        let lock = r#"
+version = 3
+
+[[package]]
+name = "genbench"
+version = "0.1.0"
+dependencies = [
+ "rand 0.8.5",
+ "slotmap",
+]
+
+[[package]]
+name = "rand"
+version = "0.7.3"
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+
+[[package]]
+name = "slotmap"
+version = "1.0.7"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "version_check",
+]
+"#;
+        let versions = dependencies(lock, "genbench");
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions["rand"], "0.8.5");
+        assert_eq!(versions["slotmap"], "1.0.7");
+        assert!(dependencies(lock, "missing").is_empty());
+    }
+
+    #[test]
+    fn current_build() {
+        let metadata = Metadata::current();
+        assert!(metadata.build.rustc.starts_with("rustc "));
+        assert_eq!(metadata.bench_profile, harness::profile().name);
+        assert_eq!(metadata.dependencies["serde"].split('.').next(), Some("1"));
+    }
+}
@@ -1,113 +1,66 @@
-use crate::extra::naive::NaiveSlotMap;
-use crate::Crate;
-use criterion::{black_box, BatchSize, Bencher};
+use crate::extra::naive::{Key, NaiveSlotMap};
+use crate::{Adapter, Arena, Capabilities, Tag};
 
-pub struct CrateMooSlotMap();
-impl Crate for CrateMooSlotMap {
-    fn name(&self) -> &'static str {
-        "NaiveSlotMap"
+pub type CrateMooSlotMap<T> = Adapter<NaiveSlotMap<Key, T>>;
+
+impl<T: Clone + 'static> Arena<T> for NaiveSlotMap<Key, T> {
+    type Key = Key;
+    const NAME: &'static str = "NaiveSlotMap";
+    const LINK: &'static str = "https://github.com/mooman219/generational_arena_bench";
+    const TAGS: &'static [Tag] = &[Tag::Dense];
+    const CAPABILITIES: Capabilities = Capabilities {
+        generational: true,
+        generational_clear: true,
+        iter_mut: true,
+        with_capacity: true,
+        retain: true,
+    };
+
+    fn new() -> Self {
+        NaiveSlotMap::new()
+    }
+
+    fn with_capacity(capacity: usize) -> Option<Self> {
+        Some(NaiveSlotMap::with_capacity(capacity))
+    }
+
+    fn insert(&mut self, value: T) -> Key {
+        self.insert(value)
+    }
+
+    fn remove(&mut self, key: Key) -> bool {
+        self.remove(key).is_some()
+    }
+
+    fn get(&self, key: Key) -> Option<&T> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        self.get_mut(key)
     }
 
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: NaiveSlotMap<usize> = NaiveSlotMap::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.iter()
     }
 
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: NaiveSlotMap<usize> = NaiveSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for a in 0..size {
-            map.remove(keys[a]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_with_keys(&self) -> impl Iterator<Item = (Key, &T)> {
+        self.iter_with_keys()
     }
 
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: NaiveSlotMap<usize> = NaiveSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.remove(keys[*a]);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+        Some(self.iter_mut())
     }
 
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: NaiveSlotMap<usize> = NaiveSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(keys[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn len(&self) -> usize {
+        self.len()
     }
 
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: NaiveSlotMap<usize> = NaiveSlotMap::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn clear(&mut self) {
+        self.clear()
     }
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: NaiveSlotMap<usize> = NaiveSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for i in lookup {
-            map.remove(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn retain(&mut self, mut f: impl FnMut(Key, &T) -> bool) {
+        self.retain(|key, value| f(key, value))
     }
 }
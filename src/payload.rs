@@ -0,0 +1,132 @@
+/// A value type stored in the arenas under test.
+///
+/// Every workload is generic over its payload so the same benchmark can be run with zero sized
+/// values, small integers, large inline components, and heap owning values with a non-trivial
+/// `Drop`.
+pub trait Payload: Clone + 'static {
+    /// Short name used to label benchmark groups.
+    const NAME: &'static str;
+
+    /// Builds the value stored for the i-th inserted element.
+    fn new(i: usize) -> Self;
+
+    /// Cheap value read by workloads to prove an element was actually reached.
+    fn checksum(&self) -> u64;
+
+    /// Cheap in-place write made by the mutable workloads. Changes the checksum unless the type
+    /// has no data.
+    fn touch(&mut self);
+}
+
+impl Payload for () {
+    const NAME: &'static str = "zst";
+
+    #[inline]
+    fn new(_: usize) -> Self {}
+
+    #[inline]
+    fn checksum(&self) -> u64 {
+        1
+    }
+
+    #[inline]
+    fn touch(&mut self) {}
+}
+
+impl Payload for u32 {
+    const NAME: &'static str = "u32";
+
+    #[inline]
+    fn new(i: usize) -> Self {
+        i as u32
+    }
+
+    #[inline]
+    fn checksum(&self) -> u64 {
+        *self as u64
+    }
+
+    #[inline]
+    fn touch(&mut self) {
+        *self = self.wrapping_add(1);
+    }
+}
+
+impl Payload for [u8; 64] {
+    const NAME: &'static str = "bytes64";
+
+    #[inline]
+    fn new(i: usize) -> Self {
+        [i as u8; 64]
+    }
+
+    #[inline]
+    fn checksum(&self) -> u64 {
+        self[0] as u64
+    }
+
+    #[inline]
+    fn touch(&mut self) {
+        self[0] = self[0].wrapping_add(1);
+    }
+}
+
+impl Payload for [u8; 256] {
+    const NAME: &'static str = "bytes256";
+
+    #[inline]
+    fn new(i: usize) -> Self {
+        [i as u8; 256]
+    }
+
+    #[inline]
+    fn checksum(&self) -> u64 {
+        self[0] as u64
+    }
+
+    #[inline]
+    fn touch(&mut self) {
+        self[0] = self[0].wrapping_add(1);
+    }
+}
+
+impl Payload for [u8; 1024] {
+    const NAME: &'static str = "bytes1024";
+
+    #[inline]
+    fn new(i: usize) -> Self {
+        [i as u8; 1024]
+    }
+
+    #[inline]
+    fn checksum(&self) -> u64 {
+        self[0] as u64
+    }
+
+    #[inline]
+    fn touch(&mut self) {
+        self[0] = self[0].wrapping_add(1);
+    }
+}
+
+impl Payload for String {
+    const NAME: &'static str = "string";
+
+    #[inline]
+    fn new(i: usize) -> Self {
+        i.to_string()
+    }
+
+    #[inline]
+    fn checksum(&self) -> u64 {
+        self.as_bytes()[0] as u64
+    }
+
+    #[inline]
+    fn touch(&mut self) {
+        // Flipping the low bit keeps an ASCII byte ASCII, so the string stays valid UTF-8.
+        if self.as_bytes().first().is_some_and(u8::is_ascii) {
+            unsafe { self.as_bytes_mut()[0] ^= 1 };
+        }
+    }
+}
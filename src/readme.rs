@@ -0,0 +1,136 @@
+use crate::harness::{Workload, WORKLOADS};
+use crate::results::Measurement;
+use crate::{Crate, Payload};
+use std::fmt::Write;
+
+/// Replaces what lies between `<!-- genbench:<name> -->` and `<!-- /genbench:<name> -->` in `doc`
+/// with `body`, keeping the markers so the section can be regenerated again.
+pub fn replace_section(doc: &str, name: &str, body: &str) -> Result<String, String> {
+    let begin = format!("<!-- genbench:{} -->", name);
+    let end = format!("<!-- /genbench:{} -->", name);
+    let start = doc
+        .find(&begin)
+        .ok_or_else(|| format!("missing {}", begin))?
+        + begin.len();
+    let stop = doc[start..]
+        .find(&end)
+        .ok_or_else(|| format!("missing {}", end))?
+        + start;
+    Ok(format!("{}\n{}{}", &doc[..start], body, &doc[stop..]))
+}
+
+/// Every registered implementation with its tags and a link to where it is developed.
+pub fn crates<T: Payload>(crates: &[Box<dyn Crate<T>>]) -> String {
+    let mut table = "| Implementation | Tags | Link |\n|---|---|---|\n".to_string();
+    for c in crates {
+        let tags: Vec<&str> = c.tags().iter().map(|t| t.name()).collect();
+        let _ = writeln!(
+            table,
+            "| {} | {} | {} |",
+            c.name(),
+            tags.join(", "),
+            c.link()
+        );
+    }
+    table
+}
+
+/// What each workload prepares and what it times.
+pub fn procedure(workloads: &[&Workload]) -> String {
+    let mut table = "| Test | Setup (untimed) | Timed |\n|---|---|---|\n".to_string();
+    for w in workloads {
+        let _ = writeln!(table, "| {} | {} | {} |", w.group, w.setup, w.timed);
+    }
+    table
+}
+
+/// One table per workload with the implementations measured at `payload` and `size`, fastest
+/// first. Times are per element, ranked by the lower bound of the mean's 95% confidence interval.
+pub fn results(measurements: &[Measurement], payload: &str, size: usize) -> String {
+    let mut text = String::new();
+    for w in WORKLOADS {
+        let mut rows: Vec<&Measurement> = measurements
+            .iter()
+            .filter(|m| m.workload == w.group && m.payload == payload && m.size == size)
+            .collect();
+        if rows.is_empty() {
+            continue;
+        }
+        let lower = |m: &Measurement| m.per_element(m.mean.lower);
+        rows.sort_by(|a, b| lower(a).total_cmp(&lower(b)));
+        let fastest = lower(rows[0]);
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        let _ = writeln!(text, "## {}/{}, size {}\n", w.group, payload, size);
+        text.push_str("| Implementation | ns/op | vs fastest | 95% CI |\n|---|---:|---:|---:|\n");
+        for m in rows {
+            let _ = writeln!(
+                text,
+                "| {} | {:.2} | {:.2}x | {:.2} - {:.2} |",
+                m.implementation,
+                lower(m),
+                lower(m) / fastest,
+                m.per_element(m.mean.lower),
+                m.per_element(m.mean.upper)
+            );
+        }
+    }
+    text
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::results::Estimate;
+
+    #[test]
+    fn replaces_between_markers() {
+        let doc = "# A\n<!-- genbench:x -->\nold\n<!-- /genbench:x -->\ntail\n";
+        let doc = replace_section(doc, "x", "new\n").unwrap();
+        assert_eq!(
+            doc,
+            "# A\n<!-- genbench:x -->\nnew\n<!-- /genbench:x -->\ntail\n"
+        );
+        assert_eq!(replace_section(&doc, "x", "new\n"), Ok(doc.clone()));
+        assert!(replace_section(&doc, "y", "").is_err());
+    }
+
+    #[test]
+    fn results_fastest_first() {
+        let measurement = |implementation: &str, lower: f64| Measurement {
+            workload: "Get".to_string(),
+            payload: "u32".to_string(),
+            implementation: implementation.to_string(),
+            size: 100,
+            elements: 100,
+            mean: Estimate {
+                point: lower + 50.0,
+                lower,
+                upper: lower + 100.0,
+            },
+            median: Estimate {
+                point: lower,
+                lower,
+                upper: lower,
+            },
+            samples: 200,
+        };
+        let text = results(
+            &[measurement("Slab", 400.0), measurement("SlotMap", 200.0)],
+            "u32",
+            100,
+        );
+        assert_eq!(
+            text,
+            "## Get/u32, size 100\n\n\
+             | Implementation | ns/op | vs fastest | 95% CI |\n|---|---:|---:|---:|\n\
+             | SlotMap | 2.00 | 1.00x | 2.00 - 3.00 |\n\
+             | Slab | 4.00 | 2.00x | 4.00 - 5.00 |\n"
+        );
+    }
+}
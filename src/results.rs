@@ -0,0 +1,213 @@
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where criterion writes its results when run from the crate root.
+pub const CRITERION_DIR: &str = "target/criterion";
+
+/// A point estimate with its 95% confidence interval, in nanoseconds per iteration.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Estimate {
+    pub point: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// One implementation measured on one workload, payload and size.
+#[derive(Clone, Debug)]
+pub struct Measurement {
+    /// Workload group, such as `Get`.
+    pub workload: String,
+    /// `Payload::NAME` of the stored type.
+    pub payload: String,
+    pub implementation: String,
+    pub size: usize,
+    /// Elements touched by one iteration, as reported through criterion's throughput.
+    pub elements: u64,
+    pub mean: Estimate,
+    pub median: Estimate,
+    /// Number of samples criterion took.
+    pub samples: usize,
+}
+
+impl Measurement {
+    /// Converts a per-iteration time into a per-element time.
+    pub fn per_element(&self, ns: f64) -> f64 {
+        ns / self.elements.max(1) as f64
+    }
+}
+
+#[derive(Deserialize)]
+struct Benchmark {
+    group_id: String,
+    function_id: Option<String>,
+    value_str: Option<String>,
+    throughput: Option<Throughput>,
+}
+
+#[derive(Deserialize)]
+enum Throughput {
+    Bytes(IgnoredAny),
+    BytesDecimal(IgnoredAny),
+    Elements(u64),
+}
+
+#[derive(Deserialize)]
+struct Estimates {
+    mean: RawEstimate,
+    median: RawEstimate,
+}
+
+#[derive(Deserialize)]
+struct Sample {
+    iters: Vec<f64>,
+}
+
+#[derive(Deserialize)]
+struct RawEstimate {
+    confidence_interval: ConfidenceInterval,
+    point_estimate: f64,
+}
+
+#[derive(Deserialize)]
+struct ConfidenceInterval {
+    lower_bound: f64,
+    upper_bound: f64,
+}
+
+impl From<RawEstimate> for Estimate {
+    fn from(e: RawEstimate) -> Self {
+        Estimate {
+            point: e.point_estimate,
+            lower: e.confidence_interval.lower_bound,
+            upper: e.confidence_interval.upper_bound,
+        }
+    }
+}
+
+/// Builds a measurement from the `benchmark.json`, `estimates.json` and `sample.json` criterion
+/// writes for one benchmark. Returns `Ok(None)` for benchmarks that are not part of the size sweep.
+pub fn parse(
+    benchmark: &str,
+    estimates: &str,
+    sample: &str,
+) -> Result<Option<Measurement>, String> {
+    let benchmark: Benchmark = serde_json::from_str(benchmark).map_err(|e| e.to_string())?;
+    let estimates: Estimates = serde_json::from_str(estimates).map_err(|e| e.to_string())?;
+    let sample: Sample = serde_json::from_str(sample).map_err(|e| e.to_string())?;
+    let (workload, payload) = match benchmark.group_id.split_once('/') {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+    let (implementation, size) = match (benchmark.function_id, benchmark.value_str) {
+        (Some(implementation), Some(size)) => match size.parse() {
+            Ok(size) => (implementation, size),
+            Err(_) => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+    let elements = match benchmark.throughput {
+        Some(Throughput::Elements(n)) => n,
+        Some(Throughput::Bytes(_)) | Some(Throughput::BytesDecimal(_)) | None => size as u64,
+    };
+    Ok(Some(Measurement {
+        workload: workload.to_string(),
+        payload: payload.to_string(),
+        implementation,
+        size,
+        elements,
+        mean: estimates.mean.into(),
+        median: estimates.median.into(),
+        samples: sample.iters.len(),
+    }))
+}
+
+/// Reads the latest result of every benchmark under `dir`.
+pub fn load(dir: &Path) -> Result<Vec<Measurement>, String> {
+    let mut found = Vec::new();
+    collect(dir, &mut found).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    found.sort();
+    let mut measurements = Vec::new();
+    for new in found {
+        let read = |file: &str| {
+            fs::read_to_string(new.join(file)).map_err(|e| format!("{}: {}", new.display(), e))
+        };
+        let (benchmark, estimates) = (read("benchmark.json")?, read("estimates.json")?);
+        match parse(&benchmark, &estimates, &read("sample.json")?) {
+            Ok(Some(measurement)) => measurements.push(measurement),
+            Ok(None) => {}
+            Err(e) => return Err(format!("{}: {}", new.display(), e)),
+        }
+    }
+    Ok(measurements)
+}
+
+/// Finds every `new` directory criterion left a finished benchmark in.
+fn collect(dir: &Path, found: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if path.ends_with("new") {
+            let files = ["benchmark.json", "estimates.json", "sample.json"];
+            if files.iter().all(|file| path.join(file).is_file()) {
+                found.push(path);
+            }
+        } else if !path.ends_with("report") {
+            collect(&path, found)?;
+        }
+    }
+    Ok(())
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ESTIMATES: &str = r#"{
+        "mean": {"confidence_interval": {"confidence_level": 0.95, "lower_bound": 900.0,
+            "upper_bound": 1100.0}, "point_estimate": 1000.0, "standard_error": 5.0},
+        "median": {"confidence_interval": {"confidence_level": 0.95, "lower_bound": 950.0,
+            "upper_bound": 1050.0}, "point_estimate": 990.0, "standard_error": 4.0},
+        "median_abs_dev": {"confidence_interval": {"confidence_level": 0.95, "lower_bound": 1.0,
+            "upper_bound": 2.0}, "point_estimate": 1.5, "standard_error": 0.1},
+        "slope": null,
+        "std_dev": {"confidence_interval": {"confidence_level": 0.95, "lower_bound": 1.0,
+            "upper_bound": 2.0}, "point_estimate": 1.5, "standard_error": 0.1}
+    }"#;
+
+    const SAMPLE: &str = r#"{"sampling_mode": "Linear", "iters": [1.0, 2.0, 3.0],
+        "times": [1000.0, 2000.0, 3000.0]}"#;
+
+    #[test]
+    fn parses_sweep_benchmark() {
+        let benchmark = r#"{"group_id": "IterHalf/u32", "function_id": "SlotMap",
+            "value_str": "1000", "throughput": {"Elements": 500},
+            "full_id": "IterHalf/u32/SlotMap/1000",
+            "directory_name": "IterHalf_u32/SlotMap/1000", "title": "IterHalf/u32/SlotMap/1000"}"#;
+        let m = parse(benchmark, ESTIMATES, SAMPLE).unwrap().unwrap();
+        assert_eq!(m.workload, "IterHalf");
+        assert_eq!(m.payload, "u32");
+        assert_eq!(m.implementation, "SlotMap");
+        assert_eq!(m.size, 1000);
+        assert_eq!(m.elements, 500);
+        assert_eq!(m.mean.lower, 900.0);
+        assert_eq!(m.median.point, 990.0);
+        assert_eq!(m.samples, 3);
+        assert_eq!(m.per_element(m.mean.lower), 1.8);
+    }
+
+    #[test]
+    fn skips_unsized_benchmark() {
+        let benchmark = r#"{"group_id": "Get", "function_id": "SlotMap", "value_str": null,
+            "throughput": null, "full_id": "Get/SlotMap", "directory_name": "Get/SlotMap",
+            "title": "Get/SlotMap"}"#;
+        assert!(parse(benchmark, ESTIMATES, SAMPLE).unwrap().is_none());
+    }
+}
@@ -0,0 +1,100 @@
+use crate::harness::Workload;
+use crate::{Crate, Payload};
+
+/// Picks implementations or workloads by name or tag, from a comma separated list of terms such
+/// as `generational,-IdVec` or `Get,iteration`. A term is a name or a tag, compared without regard to
+/// case; a leading `-` excludes what the term matches. Without any including term everything not
+/// excluded is picked, so an empty list picks everything.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Selection {
+    /// Picks everything.
+    pub fn all() -> Selection {
+        Selection::default()
+    }
+
+    /// Parses `spec`, rejecting terms that are not among the `known` names and tags.
+    pub fn parse(spec: &str, known: &[&str]) -> Result<Selection, String> {
+        let mut selection = Selection::all();
+        for term in spec.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+            let (list, word) = match term.strip_prefix('-') {
+                Some(word) => (&mut selection.exclude, word),
+                None => (&mut selection.include, term),
+            };
+            if !known.iter().any(|k| k.eq_ignore_ascii_case(word)) {
+                return Err(format!(
+                    "unknown name or tag '{}', expected one of {}",
+                    word,
+                    known.join(", ")
+                ));
+            }
+            list.push(word.to_ascii_lowercase());
+        }
+        Ok(selection)
+    }
+
+    /// True if something with this name and these tags is picked.
+    pub fn matches(&self, name: &str, tags: &[&str]) -> bool {
+        let hit = |term: &String| {
+            term.eq_ignore_ascii_case(name) || tags.iter().any(|t| term.eq_ignore_ascii_case(t))
+        };
+        (self.include.is_empty() || self.include.iter().any(hit)) && !self.exclude.iter().any(hit)
+    }
+
+    pub fn picks_crate<T: Payload>(&self, c: &dyn Crate<T>) -> bool {
+        let tags: Vec<&str> = c.tags().iter().map(|t| t.name()).collect();
+        self.matches(c.name(), &tags)
+    }
+
+    pub fn picks_workload(&self, w: &Workload) -> bool {
+        self.matches(w.group, w.tags)
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN: [&str; 4] = ["SlotMap", "IdVec", "indextree", "generational"];
+
+    #[test]
+    fn names_tags_and_exclusions() {
+        let all = Selection::parse(" ", &KNOWN).unwrap();
+        assert!(all.matches("IdVec", &[]));
+
+        let generational = Selection::parse("generational,-slotmap", &KNOWN).unwrap();
+        assert!(!generational.matches("SlotMap", &["generational"]));
+        assert!(generational.matches("indextree", &["generational", "tree"]));
+        assert!(!generational.matches("IdVec", &[]));
+
+        let without = Selection::parse("-IDVEC", &KNOWN).unwrap();
+        assert!(without.matches("SlotMap", &["generational"]));
+        assert!(!without.matches("IdVec", &[]));
+
+        assert!(Selection::parse("SlotMap,Slab", &KNOWN).is_err());
+    }
+
+    #[test]
+    fn terms_are_unambiguous() {
+        for terms in [
+            crate::harness::crate_terms(),
+            crate::harness::workload_terms(),
+        ] {
+            for (i, term) in terms.iter().enumerate() {
+                assert!(
+                    !terms[..i].iter().any(|t| t.eq_ignore_ascii_case(term)),
+                    "{} is both a name and a tag",
+                    term
+                );
+            }
+        }
+    }
+}
@@ -1,113 +1,66 @@
-use crate::Crate;
-use criterion::{black_box, BatchSize, Bencher};
+use crate::{Adapter, Arena, Capabilities, Tag};
 use slab::Slab;
 
-pub struct CrateSlab();
-impl Crate for CrateSlab {
-    fn name(&self) -> &'static str {
-        "Slab"
+pub type CrateSlab<T> = Adapter<Slab<T>>;
+
+impl<T: Clone + 'static> Arena<T> for Slab<T> {
+    type Key = usize;
+    const NAME: &'static str = "Slab";
+    const LINK: &'static str = "https://github.com/tokio-rs/slab";
+    const TAGS: &'static [Tag] = &[];
+    const CAPABILITIES: Capabilities = Capabilities {
+        generational: false,
+        generational_clear: false,
+        iter_mut: true,
+        with_capacity: true,
+        retain: true,
+    };
+
+    fn new() -> Self {
+        Slab::new()
+    }
+
+    fn with_capacity(capacity: usize) -> Option<Self> {
+        Some(Slab::with_capacity(capacity))
+    }
+
+    fn insert(&mut self, value: T) -> usize {
+        self.insert(value)
+    }
+
+    fn remove(&mut self, key: usize) -> bool {
+        self.try_remove(key).is_some()
+    }
+
+    fn get(&self, key: usize) -> Option<&T> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: usize) -> Option<&mut T> {
+        self.get_mut(key)
     }
 
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: Slab<usize> = Slab::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.iter().map(|(_, v)| v)
     }
 
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: Slab<usize> = Slab::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for a in 0..size {
-            map.remove(keys[a]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_with_keys(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.iter()
     }
 
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: Slab<usize> = Slab::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.remove(keys[*a]);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+        Some(self.iter_mut().map(|(_, v)| v))
     }
 
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: Slab<usize> = Slab::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(keys[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn len(&self) -> usize {
+        self.len()
     }
 
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: Slab<usize> = Slab::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn clear(&mut self) {
+        self.clear()
     }
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: Slab<usize> = Slab::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for i in lookup {
-            map.remove(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn retain(&mut self, mut f: impl FnMut(usize, &T) -> bool) {
+        self.retain(|key, value| f(key, value))
     }
 }
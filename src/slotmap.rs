@@ -1,333 +1,79 @@
-use crate::Crate;
-use criterion::{black_box, BatchSize, Bencher};
-use slotmap::{DefaultKey, DenseSlotMap, HopSlotMap, SlotMap};
-
-pub struct CrateSlotMap();
-impl Crate for CrateSlotMap {
-    fn name(&self) -> &'static str {
-        "SlotMap"
-    }
-
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: SlotMap<DefaultKey, usize> = SlotMap::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: SlotMap<DefaultKey, usize> = SlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for a in 0..size {
-            map.remove(keys[a]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: SlotMap<DefaultKey, usize> = SlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.remove(keys[*a]);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: SlotMap<DefaultKey, usize> = SlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(keys[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: SlotMap<DefaultKey, usize> = SlotMap::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: SlotMap<DefaultKey, usize> = SlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for i in lookup {
-            map.remove(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-}
-
-pub struct CrateHopSlotMap();
-impl Crate for CrateHopSlotMap {
-    fn name(&self) -> &'static str {
-        "HopSlotMap"
-    }
+// HopSlotMap is deprecated upstream but is still benchmarked for comparison.
+#![allow(deprecated)]
 
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: HopSlotMap<DefaultKey, usize> = HopSlotMap::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: HopSlotMap<DefaultKey, usize> = HopSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for a in 0..size {
-            map.remove(keys[a]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: HopSlotMap<DefaultKey, usize> = HopSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.remove(keys[*a]);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: HopSlotMap<DefaultKey, usize> = HopSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(keys[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: HopSlotMap<DefaultKey, usize> = HopSlotMap::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+use crate::{Adapter, Arena, Capabilities, Tag};
+use slotmap::{DefaultKey, DenseSlotMap, HopSlotMap, SlotMap};
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: HopSlotMap<DefaultKey, usize> = HopSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for i in lookup {
-            map.remove(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+pub type CrateSlotMap<T> = Adapter<SlotMap<DefaultKey, T>>;
+pub type CrateHopSlotMap<T> = Adapter<HopSlotMap<DefaultKey, T>>;
+pub type CrateDenseSlotMap<T> = Adapter<DenseSlotMap<DefaultKey, T>>;
+
+macro_rules! slotmap_arena {
+    ($map:ident, $name:literal, $tags:expr) => {
+        impl<T: Clone + 'static> Arena<T> for $map<DefaultKey, T> {
+            type Key = DefaultKey;
+            const NAME: &'static str = $name;
+            const LINK: &'static str = "https://github.com/orlp/slotmap";
+            const TAGS: &'static [Tag] = $tags;
+            const CAPABILITIES: Capabilities = Capabilities {
+                generational: true,
+                generational_clear: true,
+                iter_mut: true,
+                with_capacity: true,
+                retain: true,
+            };
+
+            fn new() -> Self {
+                $map::new()
+            }
+
+            fn with_capacity(capacity: usize) -> Option<Self> {
+                Some($map::with_capacity(capacity))
+            }
+
+            fn insert(&mut self, value: T) -> DefaultKey {
+                self.insert(value)
+            }
+
+            fn remove(&mut self, key: DefaultKey) -> bool {
+                self.remove(key).is_some()
+            }
+
+            fn get(&self, key: DefaultKey) -> Option<&T> {
+                self.get(key)
+            }
+
+            fn get_mut(&mut self, key: DefaultKey) -> Option<&mut T> {
+                self.get_mut(key)
+            }
+
+            fn iter(&self) -> impl Iterator<Item = &T> {
+                self.values()
+            }
+
+            fn iter_with_keys(&self) -> impl Iterator<Item = (DefaultKey, &T)> {
+                self.iter()
+            }
+
+            fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+                Some(self.values_mut())
+            }
+
+            fn len(&self) -> usize {
+                self.len()
+            }
+
+            fn clear(&mut self) {
+                self.clear()
+            }
+
+            fn retain(&mut self, mut f: impl FnMut(DefaultKey, &T) -> bool) {
+                self.retain(|key, value| f(key, value))
+            }
+        }
+    };
 }
 
-pub struct CrateDenseSlotMap();
-impl Crate for CrateDenseSlotMap {
-    fn name(&self) -> &'static str {
-        "DenseSlotMap"
-    }
-
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: DenseSlotMap<DefaultKey, usize> = DenseSlotMap::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: DenseSlotMap<DefaultKey, usize> = DenseSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for a in 0..size {
-            map.remove(keys[a]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: DenseSlotMap<DefaultKey, usize> = DenseSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.remove(keys[*a]);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: DenseSlotMap<DefaultKey, usize> = DenseSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(keys[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: DenseSlotMap<DefaultKey, usize> = DenseSlotMap::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: DenseSlotMap<DefaultKey, usize> = DenseSlotMap::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for i in lookup {
-            map.remove(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-}
+slotmap_arena!(SlotMap, "SlotMap", &[]);
+slotmap_arena!(HopSlotMap, "HopSlotMap", &[]);
+slotmap_arena!(DenseSlotMap, "DenseSlotMap", &[Tag::Dense]);
@@ -1,217 +1,70 @@
-use crate::Crate;
-use criterion::{black_box, BatchSize, Bencher};
+use crate::{Adapter, Arena, Capabilities, Tag};
 use stable_vec::{ExternStableVec, InlineStableVec};
 
-pub struct CrateExternStableVec();
-impl Crate for CrateExternStableVec {
-    fn name(&self) -> &'static str {
-        "ExternStableVec"
-    }
+pub type CrateExternStableVec<T> = Adapter<ExternStableVec<T>>;
+pub type CrateInlineStableVec<T> = Adapter<InlineStableVec<T>>;
 
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: ExternStableVec<usize> = ExternStableVec::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.push(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+macro_rules! stable_vec_arena {
+    ($vec:ident, $name:literal, $tags:expr) => {
+        impl<T: Clone + 'static> Arena<T> for $vec<T> {
+            type Key = usize;
+            const NAME: &'static str = $name;
+            const LINK: &'static str = "https://github.com/LukasKalbertodt/stable-vec";
+            const TAGS: &'static [Tag] = $tags;
+            const CAPABILITIES: Capabilities = Capabilities {
+                generational: false,
+                generational_clear: false,
+                iter_mut: true,
+                with_capacity: true,
+                retain: false,
+            };
 
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: ExternStableVec<usize> = ExternStableVec::new();
-        for a in 0..size {
-            map.push(a);
-        }
-        for a in 0..size {
-            map.remove(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.push(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: ExternStableVec<usize> = ExternStableVec::new();
-        for a in 0..size {
-            map.push(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.remove(*a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+            fn new() -> Self {
+                $vec::new()
+            }
 
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: ExternStableVec<usize> = ExternStableVec::new();
-        for a in 0..size {
-            map.push(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(lookup[a]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+            fn with_capacity(capacity: usize) -> Option<Self> {
+                Some($vec::with_capacity(capacity))
+            }
 
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: ExternStableVec<usize> = ExternStableVec::new();
-        for a in 0..size {
-            map.push(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+            fn insert(&mut self, value: T) -> usize {
+                self.push(value)
+            }
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: ExternStableVec<usize> = ExternStableVec::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.push(a));
-        }
-        for i in lookup {
-            map.remove(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-}
+            fn remove(&mut self, key: usize) -> bool {
+                self.remove(key).is_some()
+            }
 
-pub struct CrateInlineStableVec();
-impl Crate for CrateInlineStableVec {
-    fn name(&self) -> &'static str {
-        "InlineStableVec"
-    }
+            fn get(&self, key: usize) -> Option<&T> {
+                self.get(key)
+            }
 
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: InlineStableVec<usize> = InlineStableVec::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.push(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+            fn get_mut(&mut self, key: usize) -> Option<&mut T> {
+                self.get_mut(key)
+            }
 
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: InlineStableVec<usize> = InlineStableVec::new();
-        for a in 0..size {
-            map.push(a);
-        }
-        for a in 0..size {
-            map.remove(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.push(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+            fn iter(&self) -> impl Iterator<Item = &T> {
+                self.values()
+            }
 
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: InlineStableVec<usize> = InlineStableVec::new();
-        for a in 0..size {
-            map.push(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.remove(*a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+            fn iter_with_keys(&self) -> impl Iterator<Item = (usize, &T)> {
+                self.iter()
+            }
 
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: InlineStableVec<usize> = InlineStableVec::new();
-        for a in 0..size {
-            map.push(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(lookup[a]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+            fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+                Some(self.values_mut())
+            }
 
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: InlineStableVec<usize> = InlineStableVec::new();
-        for a in 0..size {
-            map.push(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+            fn len(&self) -> usize {
+                self.num_elements()
+            }
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: InlineStableVec<usize> = InlineStableVec::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.push(a));
+            fn clear(&mut self) {
+                self.clear()
+            }
         }
-        for i in lookup {
-            map.remove(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
+    };
 }
+
+stable_vec_arena!(ExternStableVec, "ExternStableVec", &[Tag::Bitset]);
+stable_vec_arena!(InlineStableVec, "InlineStableVec", &[]);
@@ -1,220 +1,126 @@
-use crate::Crate;
-use criterion::{black_box, BatchSize, Bencher};
-use stash::{Stash, UniqueStash};
-
-pub struct CrateStash();
-impl Crate for CrateStash {
-    fn name(&self) -> &'static str {
-        "Stash"
-    }
-
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: Stash<usize, usize> = Stash::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.put(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: Stash<usize, usize> = Stash::new();
-        for a in 0..size {
-            map.put(a);
-        }
-        for a in 0..size {
-            map.take(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.put(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: Stash<usize, usize> = Stash::new();
-        for a in 0..size {
-            map.put(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.take(*a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: Stash<usize, usize> = Stash::new();
-        for a in 0..size {
-            map.put(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(lookup[a]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: Stash<usize, usize> = Stash::new();
-        for a in 0..size {
-            map.put(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: Stash<usize, usize> = Stash::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.put(a));
-        }
-        for i in lookup {
-            map.take(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+use crate::{Adapter, Arena, Capabilities};
+use stash::{Stash, Tag, UniqueStash};
+
+pub type CrateStash<T> = Adapter<Stash<T, usize>>;
+pub type CrateUniqueStash<T> = Adapter<UniqueStash<T>>;
+
+impl<T: Clone + 'static> Arena<T> for Stash<T, usize> {
+    type Key = usize;
+    const NAME: &'static str = "Stash";
+    const LINK: &'static str = "https://github.com/Stebalien/stash-rs";
+    const TAGS: &'static [crate::Tag] = &[];
+    const CAPABILITIES: Capabilities = Capabilities {
+        generational: false,
+        generational_clear: false,
+        iter_mut: true,
+        with_capacity: true,
+        retain: false,
+    };
+
+    fn new() -> Self {
+        Stash::new()
+    }
+
+    fn with_capacity(capacity: usize) -> Option<Self> {
+        Some(Stash::with_capacity(capacity))
+    }
+
+    fn insert(&mut self, value: T) -> usize {
+        self.put(value)
+    }
+
+    fn remove(&mut self, key: usize) -> bool {
+        self.take(key).is_some()
+    }
+
+    fn get(&self, key: usize) -> Option<&T> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: usize) -> Option<&mut T> {
+        self.get_mut(key)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.values()
+    }
+
+    fn iter_with_keys(&self) -> impl Iterator<Item = (usize, &T)> {
+        self.iter()
+    }
+
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+        Some(self.values_mut())
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn clear(&mut self) {
+        self.clear()
     }
 }
 
-pub struct CrateUniqueStash();
-impl Crate for CrateUniqueStash {
-    fn name(&self) -> &'static str {
-        "UniqueStash"
-    }
-
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: UniqueStash<usize> = UniqueStash::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.put(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: UniqueStash<usize> = UniqueStash::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.put(a));
-        }
-        for a in 0..size {
-            map.take(keys[a]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.put(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: UniqueStash<usize> = UniqueStash::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.put(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.take(keys[*a]);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: UniqueStash<usize> = UniqueStash::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.put(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(keys[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: UniqueStash<usize> = UniqueStash::new();
-        for a in 0..size {
-            map.put(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
-    }
-
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: UniqueStash<usize> = UniqueStash::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.put(a));
-        }
-        for i in lookup {
-            map.take(keys[*i]);
+impl<T: Clone + 'static> Arena<T> for UniqueStash<T> {
+    type Key = Tag;
+    const NAME: &'static str = "UniqueStash";
+    const LINK: &'static str = "https://github.com/Stebalien/stash-rs";
+    const TAGS: &'static [crate::Tag] = &[];
+    const CAPABILITIES: Capabilities = Capabilities {
+        generational: true,
+        generational_clear: true,
+        iter_mut: true,
+        with_capacity: true,
+        retain: false,
+    };
+
+    fn new() -> Self {
+        UniqueStash::new()
+    }
+
+    fn with_capacity(capacity: usize) -> Option<Self> {
+        Some(UniqueStash::with_capacity(capacity))
+    }
+
+    fn insert(&mut self, value: T) -> Tag {
+        self.put(value)
+    }
+
+    fn remove(&mut self, key: Tag) -> bool {
+        self.take(key).is_some()
+    }
+
+    fn get(&self, key: Tag) -> Option<&T> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: Tag) -> Option<&mut T> {
+        self.get_mut(key)
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.values()
+    }
+
+    fn iter_with_keys(&self) -> impl Iterator<Item = (Tag, &T)> {
+        self.iter()
+    }
+
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+        Some(self.values_mut())
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn clear(&mut self) {
+        // UniqueStash::clear links every freed entry to itself, so the next put after two
+        // inserts panics. Take each element instead, which bumps the versions the same way.
+        let tags: Vec<Tag> = self.iter().map(|(tag, _)| tag).collect();
+        for tag in tags {
+            self.take(tag);
         }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
     }
 }
@@ -1,113 +1,66 @@
-use crate::Crate;
-use criterion::{black_box, BatchSize, Bencher};
-use thunderdome::Arena;
+use crate::{Adapter, Arena, Capabilities, Tag};
+use thunderdome::Index;
 
-pub struct CrateThunderdome();
-impl Crate for CrateThunderdome {
-    fn name(&self) -> &'static str {
-        "Thunderdome"
+pub type CrateThunderdome<T> = Adapter<thunderdome::Arena<T>>;
+
+impl<T: Clone + 'static> Arena<T> for thunderdome::Arena<T> {
+    type Key = Index;
+    const NAME: &'static str = "Thunderdome";
+    const LINK: &'static str = "https://github.com/LPGhatguy/thunderdome";
+    const TAGS: &'static [Tag] = &[];
+    const CAPABILITIES: Capabilities = Capabilities {
+        generational: true,
+        generational_clear: true,
+        iter_mut: true,
+        with_capacity: true,
+        retain: true,
+    };
+
+    fn new() -> Self {
+        thunderdome::Arena::new()
+    }
+
+    fn with_capacity(capacity: usize) -> Option<Self> {
+        Some(thunderdome::Arena::with_capacity(capacity))
+    }
+
+    fn insert(&mut self, value: T) -> Index {
+        self.insert(value)
+    }
+
+    fn remove(&mut self, key: Index) -> bool {
+        self.remove(key).is_some()
+    }
+
+    fn get(&self, key: Index) -> Option<&T> {
+        self.get(key)
+    }
+
+    fn get_mut(&mut self, key: Index) -> Option<&mut T> {
+        self.get_mut(key)
     }
 
-    fn insert(&self, b: &mut Bencher, size: usize) {
-        let map: Arena<usize> = Arena::new();
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.iter().map(|(_, v)| v)
     }
 
-    fn reinsert(&self, b: &mut Bencher, size: usize) {
-        let mut map: Arena<usize> = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for a in 0..size {
-            map.remove(keys[a]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    i.insert(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_with_keys(&self) -> impl Iterator<Item = (Index, &T)> {
+        self.iter()
     }
 
-    fn remove(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: Arena<usize> = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in lookup {
-                    i.remove(keys[*a]);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn iter_mut(&mut self) -> Option<impl Iterator<Item = &mut T>> {
+        Some(self.iter_mut().map(|(_, v)| v))
     }
 
-    fn get(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: Arena<usize> = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in 0..size {
-                    black_box(i.get(keys[lookup[a]]));
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn len(&self) -> usize {
+        self.len()
     }
 
-    fn iterate(&self, b: &mut Bencher, size: usize) {
-        let mut map: Arena<usize> = Arena::new();
-        for a in 0..size {
-            map.insert(a);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn clear(&mut self) {
+        self.clear()
     }
 
-    fn reiterate(&self, b: &mut Bencher, lookup: &Vec<usize>, size: usize) {
-        let mut map: Arena<usize> = Arena::new();
-        let mut keys = Vec::new();
-        for a in 0..size {
-            keys.push(map.insert(a));
-        }
-        for i in lookup {
-            map.remove(keys[*i]);
-        }
-        b.iter_batched_ref(
-            || map.clone(),
-            |i| {
-                for a in i.iter() {
-                    black_box(a);
-                }
-            },
-            BatchSize::SmallInput,
-        );
+    fn retain(&mut self, mut f: impl FnMut(Index, &T) -> bool) {
+        self.retain(|key, value| f(key, value))
     }
 }
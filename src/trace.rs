@@ -0,0 +1,404 @@
+use crate::extra::naive::{Key, NaiveSlotMap};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// First bytes of the binary form, including the format version.
+const MAGIC: &[u8; 4] = b"GBT\x01";
+
+/// First line of the text form.
+const HEADER: &str = "# genbench trace v1";
+
+/// One recorded operation. Handles are symbolic: the n-th `Insert` of a trace creates handle n,
+/// and `Remove` and `Get` refer back to it. A handle stays valid in the trace after its element
+/// is removed, so stale accesses can be replayed.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Event {
+    Insert,
+    Remove(u32),
+    Get(u32),
+    Iterate,
+}
+
+/// A sequence of operations that can be replayed against any implementation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Trace {
+    events: Vec<Event>,
+    handles: u32,
+    stale: bool,
+}
+
+impl Trace {
+    pub fn new() -> Trace {
+        Trace::default()
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Number of handles created by the trace.
+    pub fn handles(&self) -> usize {
+        self.handles as usize
+    }
+
+    /// True if the trace removes or gets a handle after it has been removed.
+    pub fn has_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Appends an event, rejecting handles that have not been created yet.
+    pub fn push(&mut self, event: Event) -> Result<(), String> {
+        match event {
+            Event::Insert => self.handles += 1,
+            Event::Remove(h) | Event::Get(h) if h >= self.handles => {
+                return Err(format!("handle {} used before it was inserted", h));
+            }
+            _ => {}
+        }
+        self.events.push(event);
+        Ok(())
+    }
+
+    /// Compact binary form: the magic bytes, then one tag byte per event followed by the handle as
+    /// a LEB128 varint for `Remove` and `Get`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        for event in &self.events {
+            match *event {
+                Event::Insert => bytes.push(0),
+                Event::Remove(h) => {
+                    bytes.push(1);
+                    write_varint(&mut bytes, h);
+                }
+                Event::Get(h) => {
+                    bytes.push(2);
+                    write_varint(&mut bytes, h);
+                }
+                Event::Iterate => bytes.push(3),
+            }
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Trace, String> {
+        let mut rest = bytes
+            .strip_prefix(MAGIC)
+            .ok_or("not a binary genbench trace")?;
+        let mut trace = Trace::new();
+        while let Some((&tag, tail)) = rest.split_first() {
+            rest = tail;
+            let event = match tag {
+                0 => Event::Insert,
+                1 => Event::Remove(read_varint(&mut rest)?),
+                2 => Event::Get(read_varint(&mut rest)?),
+                3 => Event::Iterate,
+                _ => return Err(format!("unknown event tag {}", tag)),
+            };
+            trace.push(event)?;
+        }
+        trace.finish();
+        Ok(trace)
+    }
+
+    /// Human readable form: a header line, then one event per line as `insert <handle>`,
+    /// `remove <handle>`, `get <handle>` or `iter`. Blank lines and `#` comments are ignored.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", HEADER);
+        let mut inserted = 0;
+        for event in &self.events {
+            let _ = match *event {
+                Event::Insert => {
+                    inserted += 1;
+                    writeln!(text, "insert {}", inserted - 1)
+                }
+                Event::Remove(h) => writeln!(text, "remove {}", h),
+                Event::Get(h) => writeln!(text, "get {}", h),
+                Event::Iterate => writeln!(text, "iter"),
+            };
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Trace, String> {
+        let mut trace = Trace::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |e: String| format!("line {}: {}", number + 1, e);
+            let mut words = line.split_whitespace();
+            let op = words.next().unwrap_or("");
+            let handle = match words.next() {
+                Some(word) => Some(
+                    word.parse::<u32>()
+                        .map_err(|_| error(format!("bad handle '{}'", word)))?,
+                ),
+                None => None,
+            };
+            if words.next().is_some() {
+                return Err(error(format!("trailing input in '{}'", line)));
+            }
+            let event = match (op, handle) {
+                ("insert", Some(h)) if h == trace.handles => Event::Insert,
+                ("insert", Some(h)) => {
+                    return Err(error(format!(
+                        "insert creates handle {}, not {}",
+                        trace.handles, h
+                    )))
+                }
+                ("remove", Some(h)) => Event::Remove(h),
+                ("get", Some(h)) => Event::Get(h),
+                ("iter", None) => Event::Iterate,
+                _ => return Err(error(format!("unknown event '{}'", line))),
+            };
+            trace.push(event).map_err(error)?;
+        }
+        trace.finish();
+        Ok(trace)
+    }
+
+    /// Reads either form, telling them apart by the binary magic bytes.
+    pub fn parse(bytes: &[u8]) -> Result<Trace, String> {
+        if bytes.starts_with(MAGIC) {
+            Trace::from_bytes(bytes)
+        } else {
+            let text =
+                std::str::from_utf8(bytes).map_err(|_| "trace is neither binary nor text")?;
+            Trace::from_text(text)
+        }
+    }
+
+    /// Works out whether any event touches a removed handle.
+    fn finish(&mut self) {
+        let mut live = Vec::with_capacity(self.handles());
+        self.stale = false;
+        for event in &self.events {
+            match *event {
+                Event::Insert => live.push(true),
+                Event::Remove(h) => {
+                    self.stale |= !live[h as usize];
+                    live[h as usize] = false;
+                }
+                Event::Get(h) => self.stale |= !live[h as usize],
+                Event::Iterate => {}
+            }
+        }
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u32, String> {
+    let mut value = 0u32;
+    for shift in (0..35).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or("truncated handle")?;
+        *bytes = rest;
+        // The fifth byte only has room for the top four bits of a u32.
+        if shift == 28 && byte & 0x70 != 0 {
+            return Err("handle out of range".to_string());
+        }
+        value |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("handle out of range".to_string())
+}
+
+/// A `NaiveSlotMap` that records every operation made through it, for capturing the access
+/// pattern of a real application.
+pub struct Recorder<T> {
+    map: NaiveSlotMap<Key, T>,
+    handles: HashMap<Key, u32>,
+    trace: Trace,
+}
+
+impl<T> Recorder<T> {
+    pub fn new() -> Recorder<T> {
+        Recorder {
+            map: NaiveSlotMap::new(),
+            handles: HashMap::new(),
+            trace: Trace::new(),
+        }
+    }
+
+    pub fn insert(&mut self, value: T) -> Key {
+        let key = self.map.insert(value);
+        self.handles.insert(key, self.trace.handles);
+        self.record(Event::Insert);
+        key
+    }
+
+    pub fn remove(&mut self, key: Key) -> Option<T> {
+        self.record_key(key, Event::Remove);
+        self.map.remove(key)
+    }
+
+    pub fn get(&mut self, key: Key) -> Option<&T> {
+        self.record_key(key, Event::Get);
+        self.map.get(key)
+    }
+
+    /// Recorded as a `Get`.
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        self.record_key(key, Event::Get);
+        self.map.get_mut(key)
+    }
+
+    pub fn iter(&mut self) -> std::slice::Iter<'_, T> {
+        self.record(Event::Iterate);
+        self.map.iter()
+    }
+
+    /// Recorded as an `Iterate`.
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> {
+        self.record(Event::Iterate);
+        self.map.iter_mut()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// The map being recorded, for access that should not appear in the trace.
+    pub fn map(&self) -> &NaiveSlotMap<Key, T> {
+        &self.map
+    }
+
+    pub fn trace(&mut self) -> &Trace {
+        self.trace.finish();
+        &self.trace
+    }
+
+    pub fn into_trace(mut self) -> Trace {
+        self.trace.finish();
+        self.trace
+    }
+
+    fn record(&mut self, event: Event) {
+        // Handles only ever come from this recorder, so they are always valid.
+        let _ = self.trace.push(event);
+    }
+
+    /// Keys that were never returned by this recorder have no handle and are not recorded.
+    fn record_key(&mut self, key: Key, event: fn(u32) -> Event) {
+        if let Some(&handle) = self.handles.get(&key) {
+            self.record(event(handle));
+        }
+    }
+}
+
+impl<T> Default for Recorder<T> {
+    fn default() -> Recorder<T> {
+        Recorder::new()
+    }
+}
+
+// ////////////////////////////////////////////////////////////////////////////
+// Tests
+// ////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded() -> Trace {
+        let mut recorder = Recorder::new();
+        let a = recorder.insert(1);
+        let b = recorder.insert(2);
+        recorder.get(a);
+        recorder.remove(a);
+        for value in recorder.iter_mut() {
+            *value += 1;
+        }
+        let c = recorder.insert(3);
+        recorder.get_mut(b);
+        recorder.remove(c);
+        recorder.into_trace()
+    }
+
+    #[test]
+    fn recorder_events() {
+        let trace = recorded();
+        assert_eq!(
+            trace.events(),
+            &[
+                Event::Insert,
+                Event::Insert,
+                Event::Get(0),
+                Event::Remove(0),
+                Event::Iterate,
+                Event::Insert,
+                Event::Get(1),
+                Event::Remove(2),
+            ]
+        );
+        assert_eq!(trace.handles(), 3);
+        assert!(!trace.has_stale());
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let mut trace = recorded();
+        trace.push(Event::Get(0)).unwrap();
+        for _ in 0..200 {
+            trace.push(Event::Insert).unwrap();
+        }
+        trace.push(Event::Remove(202)).unwrap();
+        trace.finish();
+        assert!(trace.has_stale());
+        assert_eq!(Trace::from_bytes(&trace.to_bytes()), Ok(trace.clone()));
+        assert_eq!(Trace::parse(&trace.to_bytes()), Ok(trace));
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let trace = recorded();
+        let text = trace.to_text();
+        assert!(text.starts_with(HEADER));
+        assert!(text.contains("\ninsert 2\n"));
+        assert_eq!(Trace::from_text(&text), Ok(trace.clone()));
+        assert_eq!(Trace::parse(text.as_bytes()), Ok(trace));
+    }
+
+    #[test]
+    fn rejects_unknown_handles() {
+        assert!(Trace::from_text("insert 0\nget 1\n").is_err());
+        assert!(Trace::from_text("insert 1\n").is_err());
+        assert!(Trace::from_bytes(b"GBT\x01\x02\x00").is_err());
+        assert!(Trace::from_bytes(b"GBT\x01\x00\x02\x80").is_err());
+    }
+
+    #[test]
+    fn varint_limits() {
+        let mut max: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x0f];
+        assert_eq!(read_varint(&mut max), Ok(u32::MAX));
+        assert!(max.is_empty());
+
+        let mut overflow: &[u8] = &[0x80, 0x80, 0x80, 0x80, 0x10];
+        assert!(read_varint(&mut overflow).is_err());
+        let mut overflow: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x7f];
+        assert!(read_varint(&mut overflow).is_err());
+        let mut too_long: &[u8] = &[0x80, 0x80, 0x80, 0x80, 0x80, 0x00];
+        assert!(read_varint(&mut too_long).is_err());
+    }
+}